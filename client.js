@@ -11,6 +11,9 @@ async function initGame() {
     const skillTreeControls = document.getElementById('skill-tree-controls');
     const playerInfo = document.getElementById('player-info');
     const itemControls = document.getElementById('item-controls');
+    const abilityControls = document.getElementById('ability-controls');
+    const duelControls = document.getElementById('duel-controls');
+    let selectedPlayer = null;
     let showingSkillTree = false;
     let wallet = null;
     let ws = null;
@@ -21,14 +24,97 @@ async function initGame() {
         game.load_game_data(JSON.stringify(data));
     }
 
+    const loadedMaps = new Set();
+    let currentLocation = null;
+
     async function loadMap(location) {
-        const response = await fetch(`/maps/${location}.json`);
-        const data = await response.json();
-        game.load_map(location, JSON.stringify(data));
+        if (loadedMaps.has(location)) {
+            return;
+        }
+        const compact = await fetch(`/maps/${location}.kmap`);
+        if (compact.ok) {
+            game.load_map(location, new Uint8Array(await compact.arrayBuffer()));
+        } else {
+            const response = await fetch(`/maps/${location}.json`);
+            const data = await response.json();
+            game.load_map(location, JSON.stringify(data));
+        }
+        loadedMaps.add(location);
+    }
+
+    async function loadPortalDestinations(location) {
+        for (const destination of game.get_portal_destinations(location)) {
+            try {
+                await loadMap(destination);
+            } catch (e) {
+                console.error(`Error loading map ${destination}:`, e);
+            }
+        }
+    }
+
+    const zoneAudio = { music: null, ambient: null };
+
+    function playZoneAudio(kind, id) {
+        const current = zoneAudio[kind];
+        if (current && current.dataset.id === id) {
+            return;
+        }
+        if (current) {
+            current.pause();
+        }
+        zoneAudio[kind] = null;
+        if (!id) {
+            return;
+        }
+        const audio = new Audio(`/audio/${id}.ogg`);
+        audio.loop = true;
+        audio.dataset.id = id;
+        audio.play().catch(e => console.log(`Audio ${id} not played:`, e));
+        zoneAudio[kind] = audio;
+    }
+
+    const pendingChunks = new Set();
+
+    async function loadChunks() {
+        for (const chunk of game.get_missing_chunks()) {
+            const key = `${chunk.location}/${chunk.x}_${chunk.y}`;
+            if (pendingChunks.has(key)) {
+                continue;
+            }
+            pendingChunks.add(key);
+            try {
+                const compact = await fetch(`/maps/${key}.kmap`);
+                if (compact.ok) {
+                    game.load_chunk(chunk.location, chunk.x, chunk.y, new Uint8Array(await compact.arrayBuffer()));
+                } else {
+                    const response = await fetch(`/maps/${key}.json`);
+                    const data = await response.json();
+                    game.load_chunk(chunk.location, chunk.x, chunk.y, JSON.stringify(data));
+                }
+            } catch (e) {
+                console.error(`Error loading chunk ${key}:`, e);
+            } finally {
+                pendingChunks.delete(key);
+            }
+        }
+    }
+
+    async function syncLocation() {
+        const character = game.get_character();
+        if (character.location !== currentLocation) {
+            currentLocation = character.location;
+            await loadMap(currentLocation);
+            await loadPortalDestinations(currentLocation);
+            const zone = game.get_zone(currentLocation);
+            playZoneAudio('music', zone.music);
+            playZoneAudio('ambient', zone.ambient);
+        }
+        await loadChunks();
     }
 
     await loadGameData();
     await loadMap('Town');
+    await loadPortalDestinations('Town');
 
     function connectWebSocket(walletAddress) {
         ws = new WebSocket(`ws://localhost:8080/${walletAddress}`);
@@ -42,6 +128,37 @@ async function initGame() {
                 if (!showingSkillTree) {
                     renderGame();
                 }
+            } else if (data.type === 'duel_request') {
+                try {
+                    combatLog.innerText = game.receive_duel_request(data.request);
+                    document.getElementById('duel-challenger').value = data.request.challenger_wallet;
+                } catch (e) {
+                    combatLog.innerText = `Error: ${e}`;
+                }
+            } else if (data.type === 'duel_result') {
+                try {
+                    combatLog.innerText = game.receive_duel_result(data.result);
+                    sendPlayerUpdate();
+                    renderGame();
+                } catch (e) {
+                    combatLog.innerText = `Error: ${e}`;
+                }
+            } else if (data.type === 'boss_credit') {
+                try {
+                    combatLog.innerText = game.receive_boss_credit(data.kill);
+                    sendPlayerUpdate();
+                    renderGame();
+                } catch (e) {
+                    combatLog.innerText = `Error: ${e}`;
+                }
+            } else if (data.type === 'pvp_result') {
+                try {
+                    combatLog.innerText = game.receive_pvp_result(data.result);
+                    sendPlayerUpdate();
+                    renderGame();
+                } catch (e) {
+                    combatLog.innerText = `Error: ${e}`;
+                }
             }
         };
         ws.onclose = ($
@@ -50,21 +167,28 @@ event) => {
         };
     }
 
-    function sendPlayerUpdate(character) {
+    function sendPlayerUpdate() {
         if (ws && ws.readyState === WebSocket.OPEN) {
-            ws.send(JSON.stringify({
-                type: 'update',
-                wallet: wallet,
-                name: character.name,
-                level: character.level,
-                x: character.x,
-                y: character.y,
-                location: character.location,
-                equipment: character.equipment,
-            }));
+            const profile = game.get_public_profile();
+            ws.send(JSON.stringify({ type: 'update', ...profile }));
+        }
+    }
+
+    function shareBossCredit() {
+        const kill = game.take_last_boss_kill();
+        if (kill && kill.participants.length > 0 && ws && ws.readyState === WebSocket.OPEN) {
+            ws.send(JSON.stringify({ type: 'boss_credit', kill }));
         }
     }
 
+    function itemName(instance) {
+        if (!instance) {
+            return 'None';
+        }
+        const name = game.item_name(instance.item_id) || `Item ${instance.item_id}`;
+        return `${instance.rarity} ${name} (${instance.durability}/${instance.max_durability})`;
+    }
+
     function renderGame() {
         game.render_game('game-canvas', showingSkillTree);
     }
@@ -79,7 +203,10 @@ event) => {
         ctx.fillText(`Profession: ${character.profession}`, 10, 90);
         ctx.fillText(`Gold: ${character.gold}`, 10, 110);
         ctx.fillText(`Skill Points: ${character.skill_points}`, 10, 130);
-        ctx.fillText(`Location: ${character.location}`, 10, 150);
+        ctx.fillText(`Location: ${game.describe_zone(character.location)}`, 10, 150);
+        ctx.fillText(`HP: ${character.health}`, 10, 170);
+        ctx.fillText(`Mana: ${character.mana}`, 10, 190);
+        ctx.fillText(`Karma: ${character.karma}${character.hostile_until !== null ? ' (Hostile)' : ''}`, 10, 210);
 
         document.getElementById('base-stats').innerHTML = `
             Strength: ${character.strength}<br>
@@ -106,23 +233,34 @@ event) => {
             Fishing: ${character.fishing}<br>
             Alchemy: ${character.alchemy}
         `;
+        const sheet = game.get_stat_sheet();
+        document.getElementById('derived-stats').innerHTML = `
+            Max HP: ${sheet.max_health}<br>
+            Max Mana: ${sheet.max_mana}<br>
+            Attack: ${sheet.attack}<br>
+            Defense: ${sheet.defense}<br>
+            Crit Chance: ${Math.round(sheet.crit_chance * 100)}%<br>
+            Magic Resistance: ${sheet.magic_resistance}
+        `;
         document.getElementById('other-stats').innerHTML = `
             Race: ${character.race}<br>
             Level: ${character.level}<br>
             Experience: ${character.experience}<br>
             Guild: ${character.guild || 'None'}<br>
-            Gold: ${character.gold}
+            Gold: ${character.gold}<br>
+            HP: ${character.health}<br>
+            Mana: ${character.mana}
         `;
         document.getElementById('equipment').innerHTML = `
-            Armor: ${character.equipment.armor || 'None'}<br>
-            Helmet: ${character.equipment.helmet || 'None'}<br>
-            Amulet: ${character.equipment.amulet || 'None'}<br>
-            Gloves: ${character.equipment.gloves || 'None'}<br>
-            Ring: ${character.equipment.ring || 'None'}<br>
-            Weapon: ${character.equipment.weapon || 'None'}<br>
-            Shield: ${character.equipment.shield || 'None'}<br>
-            Legs: ${character.equipment.legs || 'None'}<br>
-            Boots: ${character.equipment.boots || 'None'}
+            Armor: ${itemName(character.equipment.armor)}<br>
+            Helmet: ${itemName(character.equipment.helmet)}<br>
+            Amulet: ${itemName(character.equipment.amulet)}<br>
+            Gloves: ${itemName(character.equipment.gloves)}<br>
+            Ring: ${itemName(character.equipment.ring)}<br>
+            Weapon: ${itemName(character.equipment.weapon)}<br>
+            Shield: ${itemName(character.equipment.shield)}<br>
+            Legs: ${itemName(character.equipment.legs)}<br>
+            Boots: ${itemName(character.equipment.boots)}
         `;
     }
 
@@ -159,7 +297,23 @@ event) => {
             equipmentWindow.classList.remove('hidden');
             skillTreeControls.classList.remove('hidden');
             itemControls.classList.remove('hidden');
-            sendPlayerUpdate(character);
+            abilityControls.classList.remove('hidden');
+            duelControls.classList.remove('hidden');
+            sendPlayerUpdate();
+            setInterval(() => {
+                const moving = game.is_moving();
+                const result = game.tick();
+                if (result) {
+                    combatLog.innerText = result;
+                }
+                syncLocation().catch(e => console.error('Error loading maps:', e));
+                if (moving) {
+                    sendPlayerUpdate();
+                }
+                if (!showingSkillTree) {
+                    renderGame();
+                }
+            }, 1000);
             console.log('Character NFT creation on Solana TBD');
         } catch (e) {
             console.error('Error creating character:', e);
@@ -168,21 +322,74 @@ event) => {
 
     document.getElementById('explore').addEventListener('click', () => {
         try {
-            const result = game.fight_monster();
+            const result = game.fight_monster(null);
             combatLog.innerText = result;
+            shareBossCredit();
             const character = game.get_character();
             if (!showingSkillTree) {
                 renderGame();
             } else {
                 renderSkillTree();
             }
-            sendPlayerUpdate(character);
+            sendPlayerUpdate();
             console.log('Character NFT update on Solana TBD');
         } catch (e) {
             console.error('Error fighting monster:', e);
         }
     });
 
+    document.getElementById('flee').addEventListener('click', () => {
+        try {
+            const result = game.flee_encounter();
+            combatLog.innerText = result;
+            const character = game.get_character();
+            if (!showingSkillTree) {
+                renderGame();
+            }
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('respawn').addEventListener('click', async () => {
+        try {
+            const result = game.respawn();
+            await syncLocation();
+            combatLog.innerText = result;
+            if (!showingSkillTree) {
+                renderGame();
+            }
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('temple-resurrect').addEventListener('click', () => {
+        try {
+            const result = game.temple_resurrect();
+            combatLog.innerText = result;
+            if (!showingSkillTree) {
+                renderGame();
+            }
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('gather').addEventListener('click', () => {
+        try {
+            const result = game.gather();
+            combatLog.innerText = result;
+            const character = game.get_character();
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
     document.getElementById('toggle-skills').addEventListener('click', () => {
         skillsMenu.classList.toggle('hidden');
         equipmentWindow.classList.toggle('hidden');
@@ -210,7 +417,7 @@ event) => {
             } else {
                 renderGame();
             }
-            sendPlayerUpdate(character);
+            sendPlayerUpdate();
             console.log('Character NFT update on Solana TBD');
         } catch (e) {
             combatLog.innerText = `Error: ${e}`;
@@ -223,22 +430,79 @@ event) => {
             game.equip_item(itemId);
             const character = game.get_character();
             renderGame();
-            sendPlayerUpdate(character);
+            sendPlayerUpdate();
             console.log('Character NFT update on Solana TBD');
         } catch (e) {
             combatLog.innerText = `Error: ${e}`;
         }
     });
 
+    document.getElementById('unequip-item').addEventListener('click', () => {
+        const slot = document.getElementById('unequip-slot').value;
+        try {
+            game.unequip_item(slot);
+            const character = game.get_character();
+            renderGame();
+            sendPlayerUpdate();
+            console.log('Character NFT update on Solana TBD');
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('repair-item').addEventListener('click', () => {
+        const instanceId = parseInt(document.getElementById('instance-id').value);
+        try {
+            const result = game.repair_item(instanceId);
+            combatLog.innerText = result;
+            const character = game.get_character();
+            renderGame();
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('use-item').addEventListener('click', () => {
+        const itemId = parseInt(document.getElementById('item-id').value);
+        try {
+            const result = game.use_item(itemId);
+            combatLog.innerText = result;
+            const character = game.get_character();
+            renderGame();
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('use-ability').addEventListener('click', () => {
+        const abilityId = parseInt(document.getElementById('ability-id').value);
+        const target = document.getElementById('ability-target').value;
+        try {
+            const result = game.use_ability(abilityId, target);
+            shareBossCredit();
+            combatLog.innerText = result;
+            const character = game.get_character();
+            renderGame();
+            sendPlayerUpdate();
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
     function updatePosition(dx, dy) {
         const character = game.get_character();
         const newX = character.x + dx;
         const newY = character.y + dy;
-        const location = document.getElementById('location').value;
         try {
-            game.update_position(newX, newY, location);
+            const result = game.update_position(newX, newY);
+            if (result) {
+                combatLog.innerText = result;
+            }
+            syncLocation().catch(e => console.error('Error loading maps:', e));
             renderGame();
-            sendPlayerUpdate(character);
+            sendPlayerUpdate();
             console.log('Character NFT update on Solana TBD');
         } catch (e) {
             combatLog.innerText = `Error: ${e}`;
@@ -250,15 +514,52 @@ event) => {
     document.getElementById('move-left').addEventListener('click', () => updatePosition(-1, 0));
     document.getElementById('move-right').addEventListener('click', () => updatePosition(1, 0));
 
-    document.getElementById('location').addEventListener('change', async () => {
-        const character = game.get_character();
-        const location = document.getElementById('location').value;
+
+    document.getElementById('challenge-duel').addEventListener('click', () => {
+        try {
+            if (!selectedPlayer) {
+                throw 'No player selected';
+            }
+            const ranked = document.getElementById('duel-ranked').checked;
+            const request = game.challenge_player(selectedPlayer, ranked);
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                ws.send(JSON.stringify({ type: 'duel_request', target: selectedPlayer, request }));
+            }
+            combatLog.innerText = `Challenged ${selectedPlayer} to ${ranked ? 'a ranked arena duel' : 'a duel'}.`;
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('accept-duel').addEventListener('click', () => {
+        const challenger = document.getElementById('duel-challenger').value;
         try {
-            await loadMap(location);
-            game.update_position(character.x, character.y, location);
+            const result = game.accept_duel(challenger);
+            combatLog.innerText = result;
+            if (ws && ws.readyState === WebSocket.OPEN) {
+                ws.send(JSON.stringify({ type: 'duel_result', target: challenger, result: game.get_last_duel_result() }));
+            }
+            sendPlayerUpdate();
             renderGame();
-            sendPlayerUpdate(character);
-            console.log('Character NFT update on Solana TBD');
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('decline-duel').addEventListener('click', () => {
+        try {
+            combatLog.innerText = game.decline_duel(document.getElementById('duel-challenger').value);
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
+    });
+
+    document.getElementById('refresh-leaderboard').addEventListener('click', () => {
+        try {
+            const entries = game.get_leaderboard(10);
+            document.getElementById('leaderboard').innerHTML = entries
+                .map((e) => `<li>${e.name} (Level ${e.level}) - ${e.rating} [${e.wins}W/${e.losses}L/${e.draws}D]</li>`)
+                .join('');
         } catch (e) {
             combatLog.innerText = `Error: ${e}`;
         }
@@ -271,28 +572,37 @@ event) => {
         const character = game.get_character();
         const players = game.other_players;
         const tileSize = 32;
-        const tileX = Math.floor((mx - canvas.width / 2) / tileSize + (my - canvas.height / 4) / (tileSize / 2));
-        const tileY = Math.floor((my - canvas.height / 4) / (tileSize / 2) - (mx - canvas.width / 2) / tileSize);
+        const [originX, originY] = game.camera_origin();
+        const tileX = Math.floor((mx - canvas.width / 2) / tileSize + (my - canvas.height / 4) / (tileSize / 2) + originX);
+        const tileY = Math.floor((my - canvas.height / 4) / (tileSize / 2) - (mx - canvas.width / 2) / tileSize + originY);
 
         for (const player of players) {
             if (Math.abs(player.x - tileX) < 1 && Math.abs(player.y - tileY) < 1 && player.location === character.location) {
                 playerInfo.classList.remove('hidden');
+                selectedPlayer = player.wallet;
                 document.getElementById('player-details').innerHTML = `
                     Name: ${player.name}<br>
                     Level: ${player.level}<br>
-                    Armor: ${player.equipment.armor || 'None'}<br>
-                    Helmet: ${player.equipment.helmet || 'None'}<br>
-                    Amulet: ${player.equipment.amulet || 'None'}<br>
-                    Gloves: ${player.equipment.gloves || 'None'}<br>
-                    Ring: ${player.equipment.ring || 'None'}<br>
-                    Weapon: ${player.equipment.weapon || 'None'}<br>
-                    Shield: ${player.equipment.shield || 'None'}<br>
-                    Legs: ${player.equipment.legs || 'None'}<br>
-                    Boots: ${player.equipment.boots || 'None'}
+                    Armor: ${itemName(player.equipment.armor)}<br>
+                    Helmet: ${itemName(player.equipment.helmet)}<br>
+                    Amulet: ${itemName(player.equipment.amulet)}<br>
+                    Gloves: ${itemName(player.equipment.gloves)}<br>
+                    Ring: ${itemName(player.equipment.ring)}<br>
+                    Weapon: ${itemName(player.equipment.weapon)}<br>
+                    Shield: ${itemName(player.equipment.shield)}<br>
+                    Legs: ${itemName(player.equipment.legs)}<br>
+                    Boots: ${itemName(player.equipment.boots)}
                 `;
                 return;
             }
         }
+
+        try {
+            const path = game.move_to(tileX, tileY);
+            combatLog.innerText = path.length > 0 ? `Moving to (${tileX}, ${tileY}), ${path.length} steps.` : 'Already there.';
+        } catch (e) {
+            combatLog.innerText = `Error: ${e}`;
+        }
     });
 
     canvas.addEventListener('contextmenu', (event) => {
@@ -303,16 +613,19 @@ event) => {
         const character = game.get_character();
         const players = game.other_players;
         const tileSize = 32;
-        const tileX = Math.floor((mx - canvas.width / 2) / tileSize + (my - canvas.height / 4) / (tileSize / 2));
-        const tileY = Math.floor((my - canvas.height / 4) / (tileSize / 2) - (mx - canvas.width / 2) / tileSize);
+        const [originX, originY] = game.camera_origin();
+        const tileX = Math.floor((mx - canvas.width / 2) / tileSize + (my - canvas.height / 4) / (tileSize / 2) + originX);
+        const tileY = Math.floor((my - canvas.height / 4) / (tileSize / 2) - (mx - canvas.width / 2) / tileSize + originY);
 
         for (const player of players) {
             if (Math.abs(player.x - tileX) < 1 && Math.abs(player.y - tileY) < 1 && player.location === character.location) {
                 try {
                     const result = game.fight_player(player.wallet);
                     combatLog.innerText = result;
-                    const updatedCharacter = game.get_character();
-                    sendPlayerUpdate(updatedCharacter);
+                    if (ws && ws.readyState === WebSocket.OPEN) {
+                        ws.send(JSON.stringify({ type: 'pvp_result', target: player.wallet, result: game.get_last_pvp_result() }));
+                    }
+                    sendPlayerUpdate();
                     renderGame();
                     console.log('Character NFT update on Solana TBD');
                 } catch (e) {
@@ -321,6 +634,20 @@ event) => {
                 return;
             }
         }
+
+        for (const monster of game.get_monsters()) {
+            if (Math.abs(monster.x - tileX) < 1 && Math.abs(monster.y - tileY) < 1) {
+                try {
+                    combatLog.innerText = game.fight_monster(monster.instance_id);
+                    shareBossCredit();
+                    sendPlayerUpdate();
+                    renderGame();
+                } catch (e) {
+                    combatLog.innerText = `Error: ${e}`;
+                }
+                return;
+            }
+        }
     });
 }
 
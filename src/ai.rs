@@ -0,0 +1,187 @@
+use serde::{Serialize, Deserialize};
+
+use crate::pathfinding::{self, tile_of, Terrain};
+use crate::spawns::{self, MonsterInstance};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Behavior {
+    #[default]
+    Passive,
+    Aggressive,
+    Cowardly,
+    Ranged,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AiState {
+    #[default]
+    Idle,
+    Chasing,
+    Attacking,
+    Fleeing,
+    Returning,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AiProfile {
+    #[serde(default)]
+    pub behavior: Behavior,
+    #[serde(default = "default_aggro_radius")]
+    pub aggro_radius: f32,
+    #[serde(default = "default_leash_radius")]
+    pub leash_radius: f32,
+    #[serde(default)]
+    pub flee_health_percent: u32,
+    #[serde(default = "default_attack_range")]
+    pub attack_range: f32,
+    #[serde(default)]
+    pub keep_distance: f32,
+    #[serde(default = "default_move_interval")]
+    pub move_interval_ticks: u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AiAction {
+    None,
+    Engage,
+}
+
+fn default_aggro_radius() -> f32 {
+    4.0
+}
+
+fn default_leash_radius() -> f32 {
+    8.0
+}
+
+fn default_attack_range() -> f32 {
+    spawns::ENGAGE_RANGE
+}
+
+fn default_move_interval() -> u64 {
+    1
+}
+
+impl Default for AiProfile {
+    fn default() -> Self {
+        AiProfile {
+            behavior: Behavior::default(),
+            aggro_radius: default_aggro_radius(),
+            leash_radius: default_leash_radius(),
+            flee_health_percent: 0,
+            attack_range: default_attack_range(),
+            keep_distance: 0.0,
+            move_interval_ticks: default_move_interval(),
+        }
+    }
+}
+
+impl AiProfile {
+    pub fn should_flee(&self, health: u32, max_health: u32) -> bool {
+        self.flee_health_percent > 0 && health > 0 && health * 100 <= max_health * self.flee_health_percent
+    }
+
+    pub fn validate(&self, monster: &str) -> Result<(), String> {
+        if self.behavior == Behavior::Ranged && self.keep_distance >= self.attack_range {
+            return Err(format!("Ranged monster {} must keep a distance shorter than its attack range", monster));
+        }
+        Ok(())
+    }
+}
+
+pub fn step(instance: &mut MonsterInstance, profile: &AiProfile, terrain: &Terrain, target: Option<(f32, f32)>, tick: u64) -> AiAction {
+    if tick < instance.last_action_tick + profile.move_interval_ticks {
+        return AiAction::None;
+    }
+
+    let home = (instance.spawn_x, instance.spawn_y);
+    let from_home = instance.distance_to(home.0, home.1);
+    if instance.state != AiState::Returning && from_home > profile.leash_radius {
+        instance.state = AiState::Returning;
+    }
+
+    match instance.state {
+        AiState::Returning => {
+            if from_home < 1.0 {
+                instance.state = AiState::Idle;
+                instance.health = instance.max_health;
+            } else {
+                move_toward(instance, terrain, home, tick);
+            }
+            return AiAction::None;
+        }
+        AiState::Fleeing => {
+            match target.filter(|&(x, y)| instance.distance_to(x, y) <= profile.aggro_radius) {
+                Some(threat) => move_away(instance, terrain, threat, tick),
+                None => instance.state = AiState::Returning,
+            }
+            return AiAction::None;
+        }
+        _ => {}
+    }
+
+    let Some((tx, ty)) = target else {
+        instance.state = if from_home < 1.0 { AiState::Idle } else { AiState::Returning };
+        return AiAction::None;
+    };
+    let distance = instance.distance_to(tx, ty);
+
+    match profile.behavior {
+        Behavior::Passive => {
+            instance.state = if from_home < 1.0 { AiState::Idle } else { AiState::Returning };
+            AiAction::None
+        }
+        Behavior::Cowardly => {
+            if distance <= profile.aggro_radius {
+                instance.state = AiState::Fleeing;
+                move_away(instance, terrain, (tx, ty), tick);
+            }
+            AiAction::None
+        }
+        Behavior::Aggressive | Behavior::Ranged => {
+            let distance = if profile.behavior == Behavior::Ranged && distance < profile.keep_distance {
+                move_away(instance, terrain, (tx, ty), tick);
+                instance.distance_to(tx, ty)
+            } else {
+                distance
+            };
+            if distance <= profile.attack_range {
+                instance.state = AiState::Attacking;
+                AiAction::Engage
+            } else if distance <= profile.aggro_radius || instance.state == AiState::Chasing {
+                instance.state = AiState::Chasing;
+                move_toward(instance, terrain, (tx, ty), tick);
+                AiAction::None
+            } else {
+                instance.state = if from_home < 1.0 { AiState::Idle } else { AiState::Returning };
+                AiAction::None
+            }
+        }
+    }
+}
+
+fn move_toward(instance: &mut MonsterInstance, terrain: &Terrain, goal: (f32, f32), tick: u64) {
+    let path = pathfinding::find_path(terrain, tile_of(instance.x, instance.y), tile_of(goal.0, goal.1));
+    if let Some(&(x, y)) = path.as_ref().and_then(|p| p.first()) {
+        instance.x = x as f32;
+        instance.y = y as f32;
+        instance.last_action_tick = tick;
+    }
+}
+
+fn move_away(instance: &mut MonsterInstance, terrain: &Terrain, threat: (f32, f32), tick: u64) {
+    let (x, y) = tile_of(instance.x, instance.y);
+    let best = pathfinding::neighbors(terrain, (x, y))
+        .into_iter()
+        .map(|(nx, ny)| ((nx, ny), (nx as f32 - threat.0).powi(2) + (ny as f32 - threat.1).powi(2)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    if let Some(((nx, ny), new_distance)) = best {
+        if new_distance > instance.distance_to(threat.0, threat.1).powi(2) {
+            instance.x = nx as f32;
+            instance.y = ny as f32;
+            instance.last_action_tick = tick;
+        }
+    }
+}
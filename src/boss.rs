@@ -0,0 +1,83 @@
+use serde::{Serialize, Deserialize};
+
+use crate::AbilityEffect;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BossAbility {
+    pub name: String,
+    pub every_rounds: u32,
+    pub effect: AbilityEffect,
+    #[serde(default)]
+    pub applies: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BossPhase {
+    pub name: String,
+    pub health_percent: u32,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub attack_bonus: u32,
+    #[serde(default)]
+    pub abilities: Vec<BossAbility>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BossProfile {
+    pub phases: Vec<BossPhase>,
+    #[serde(default)]
+    pub enrage_rounds: Option<u32>,
+    #[serde(default = "default_enrage_attack_percent")]
+    pub enrage_attack_percent: u32,
+    #[serde(default = "default_lockout_ticks")]
+    pub lockout_ticks: u64,
+    #[serde(default = "default_credit_radius")]
+    pub credit_radius: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BossKill {
+    pub monster_id: u32,
+    pub boss_name: String,
+    pub location: String,
+    pub killer_wallet: String,
+    pub killer_name: String,
+    pub participants: Vec<String>,
+    pub experience_share: u32,
+}
+
+fn default_enrage_attack_percent() -> u32 {
+    200
+}
+
+fn default_lockout_ticks() -> u64 {
+    3600
+}
+
+fn default_credit_radius() -> f32 {
+    10.0
+}
+
+impl BossProfile {
+    pub fn phase_for(&self, health: u32, max_health: u32) -> usize {
+        self.phases.iter().rposition(|p| health as u64 * 100 <= max_health as u64 * p.health_percent as u64).unwrap_or(0)
+    }
+
+    pub fn validate(&self, boss_name: &str) -> Result<(), String> {
+        if self.phases.is_empty() {
+            return Err(format!("Boss {} has no phases", boss_name));
+        }
+        if self.phases.windows(2).any(|w| w[1].health_percent >= w[0].health_percent) {
+            return Err(format!("Boss {} phases must have descending health thresholds", boss_name));
+        }
+        if let Some(ability) = self.phases.iter().flat_map(|p| &p.abilities).find(|a| a.every_rounds == 0) {
+            return Err(format!("Boss {} ability {} must recur every round or more", boss_name, ability.name));
+        }
+        Ok(())
+    }
+}
+
+pub fn is_locked_out(lockouts: &std::collections::HashMap<u32, u64>, monster_id: u32, tick: u64) -> Option<u64> {
+    lockouts.get(&monster_id).filter(|&&until| tick < until).map(|until| until - tick)
+}
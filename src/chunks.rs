@@ -0,0 +1,74 @@
+use serde::{Serialize, Deserialize};
+
+use crate::Map;
+
+pub const LOAD_RADIUS: u32 = 1;
+pub const UNLOAD_RADIUS: u32 = 2;
+pub const VIEW_RADIUS: usize = 10;
+const CAMERA_ANCHOR: f32 = 5.0;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Chunk {
+    pub tiles: Vec<Vec<String>>,
+    pub collisions: Vec<Vec<bool>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChunkRequest {
+    pub location: String,
+    pub x: u32,
+    pub y: u32,
+}
+
+pub fn chunk_of(size: u32, x: f32, y: f32) -> (u32, u32) {
+    (x.max(0.0) as u32 / size, y.max(0.0) as u32 / size)
+}
+
+pub fn chunk_count(map: &Map, size: u32) -> (u32, u32) {
+    (map.width.div_ceil(size), map.height.div_ceil(size))
+}
+
+pub fn chunk_dimensions(map: &Map, size: u32, (cx, cy): (u32, u32)) -> (u32, u32) {
+    ((map.width - cx * size).min(size), (map.height - cy * size).min(size))
+}
+
+pub fn chunks_around(map: &Map, x: f32, y: f32, radius: u32) -> Vec<(u32, u32)> {
+    let Some(size) = map.chunk_size.filter(|&s| s > 0) else {
+        return Vec::new();
+    };
+    let (cx, cy) = chunk_of(size, x, y);
+    let (columns, rows) = chunk_count(map, size);
+    let mut result = Vec::new();
+    for y in cy.saturating_sub(radius)..(cy + radius + 1).min(rows) {
+        for x in cx.saturating_sub(radius)..(cx + radius + 1).min(columns) {
+            result.push((x, y));
+        }
+    }
+    result
+}
+
+pub fn within(size: u32, chunk: (u32, u32), x: f32, y: f32, radius: u32) -> bool {
+    let (cx, cy) = chunk_of(size, x, y);
+    chunk.0.abs_diff(cx) <= radius && chunk.1.abs_diff(cy) <= radius
+}
+
+pub fn camera_origin(map: &Map, x: f32, y: f32) -> (f32, f32) {
+    if map.chunk_size.is_some() {
+        (x - CAMERA_ANCHOR, y - CAMERA_ANCHOR)
+    } else {
+        (0.0, 0.0)
+    }
+}
+
+pub fn view_bounds(map: &Map, x: f32, y: f32) -> (usize, usize, usize, usize) {
+    if map.chunk_size.is_none() {
+        return (0, 0, map.width as usize, map.height as usize);
+    }
+    let (px, py) = (x.max(0.0) as usize, y.max(0.0) as usize);
+    (
+        px.saturating_sub(VIEW_RADIUS),
+        py.saturating_sub(VIEW_RADIUS),
+        (px + VIEW_RADIUS + 1).min(map.width as usize),
+        (py + VIEW_RADIUS + 1).min(map.height as usize),
+    )
+}
@@ -0,0 +1,201 @@
+use serde::{Serialize, Deserialize};
+use rand::prelude::*;
+
+use crate::Monster;
+use crate::effects::{self, StatusEffect};
+use crate::loot::LootDrop;
+use crate::spawns::MonsterInstance;
+use crate::stats::StatSheet;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum EncounterState {
+    Ongoing,
+    Won,
+    Fled,
+    MonsterFled,
+    Died,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Encounter {
+    pub monster_id: u32,
+    pub instance_id: u32,
+    pub monster_name: String,
+    pub monster_level: u32,
+    pub monster_health: u32,
+    pub monster_max_health: u32,
+    pub round: u32,
+    pub phase: usize,
+    pub enraged: bool,
+    pub state: EncounterState,
+    pub monster_effects: Vec<StatusEffect>,
+    pub loot: Vec<LootDrop>,
+    pub log: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Combatant {
+    pub attack: u32,
+    pub strength: u32,
+    pub accuracy: u32,
+    pub agility: u32,
+    pub luck: u32,
+    pub defense: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct AttackRoll {
+    pub hit: bool,
+    pub crit: bool,
+    pub damage: u32,
+}
+
+impl Encounter {
+    pub fn new(monster: &Monster, instance: &MonsterInstance) -> Encounter {
+        Encounter {
+            monster_id: monster.id,
+            instance_id: instance.instance_id,
+            monster_name: monster.name.clone(),
+            monster_level: monster.level,
+            monster_health: instance.health,
+            monster_max_health: instance.max_health,
+            round: 0,
+            phase: 0,
+            enraged: false,
+            state: EncounterState::Ongoing,
+            monster_effects: Vec::new(),
+            loot: Vec::new(),
+            log: Vec::new(),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state != EncounterState::Ongoing
+    }
+}
+
+impl Combatant {
+    pub fn from_sheet(sheet: &StatSheet) -> Combatant {
+        Combatant {
+            attack: sheet.attack,
+            strength: sheet.stat("strength"),
+            accuracy: sheet.stat("accuracy"),
+            agility: sheet.stat("agility"),
+            luck: sheet.stat("luck"),
+            defense: sheet.defense,
+        }
+    }
+
+    pub fn from_monster(monster: &Monster, active: &[StatusEffect]) -> Combatant {
+        let strength = effects::modified(monster_stat(monster, "strength"), active, "strength");
+        let accuracy = effects::modified(monster_stat(monster, "accuracy"), active, "accuracy");
+        Combatant {
+            attack: strength / 2 + accuracy / 4 + effects::modified(monster_stat(monster, "attack"), active, "attack"),
+            strength,
+            accuracy,
+            agility: effects::modified(monster_stat(monster, "agility"), active, "agility"),
+            luck: effects::modified(monster_stat(monster, "luck"), active, "luck"),
+            defense: effects::modified(monster_stat(monster, "defense"), active, "defense"),
+        }
+    }
+}
+
+pub fn monster_stat(monster: &Monster, stat: &str) -> u32 {
+    *monster.stats.get(stat).unwrap_or(&0)
+}
+
+pub fn hit_chance(attacker: &Combatant, defender: &Combatant) -> f64 {
+    let diff = attacker.accuracy as f64 - defender.agility as f64;
+    (0.75 + diff * 0.02).clamp(0.05, 0.95)
+}
+
+pub fn crit_chance(attacker: &Combatant) -> f64 {
+    (0.05 + attacker.luck as f64 * 0.01).clamp(0.0, 0.5)
+}
+
+pub fn roll_attack(rng: &mut impl Rng, attacker: &Combatant, defender: &Combatant) -> AttackRoll {
+    if !rng.gen_bool(hit_chance(attacker, defender)) {
+        return AttackRoll { hit: false, crit: false, damage: 0 };
+    }
+
+    let base = attacker.attack;
+    let spread = rng.gen_range(0..=attacker.strength / 4);
+    let crit = rng.gen_bool(crit_chance(attacker));
+    let damage = ((base + spread) * if crit { 2 } else { 1 }).saturating_sub(defender.defense).max(1);
+    AttackRoll { hit: true, crit, damage }
+}
+
+pub fn flee_chance(character: &Combatant, monster_level: u32) -> f64 {
+    let diff = character.agility as f64 - (monster_level * 2) as f64;
+    (0.5 + diff * 0.02).clamp(0.1, 0.9)
+}
+
+pub fn describe_attack(attacker: &str, defender: &str, roll: &AttackRoll) -> String {
+    if !roll.hit {
+        format!("{} attacks {} but misses.", attacker, defender)
+    } else if roll.crit {
+        format!("{} critically hits {} for {} damage!", attacker, defender, roll.damage)
+    } else {
+        format!("{} hits {} for {} damage.", attacker, defender, roll.damage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fighter(accuracy: u32, agility: u32, luck: u32) -> Combatant {
+        Combatant { attack: 10, strength: 8, accuracy, agility, luck, defense: 3 }
+    }
+
+    #[test]
+    fn hit_chance_is_clamped() {
+        assert!((hit_chance(&fighter(10, 0, 0), &fighter(0, 10, 0)) - 0.75).abs() < 1e-9);
+        assert_eq!(hit_chance(&fighter(200, 0, 0), &fighter(0, 0, 0)), 0.95);
+        assert_eq!(hit_chance(&fighter(0, 0, 0), &fighter(0, 200, 0)), 0.05);
+    }
+
+    #[test]
+    fn crit_chance_grows_with_luck() {
+        assert!((crit_chance(&fighter(0, 0, 0)) - 0.05).abs() < 1e-9);
+        assert_eq!(crit_chance(&fighter(0, 0, 100)), 0.5);
+    }
+
+    #[test]
+    fn flee_chance_is_clamped() {
+        assert!((flee_chance(&fighter(0, 10, 0), 5) - 0.5).abs() < 1e-9);
+        assert_eq!(flee_chance(&fighter(0, 0, 0), 50), 0.1);
+        assert_eq!(flee_chance(&fighter(0, 200, 0), 1), 0.9);
+    }
+
+    #[test]
+    fn hits_always_deal_damage() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let attacker = Combatant { attack: 1, strength: 0, ..fighter(200, 0, 0) };
+        let defender = Combatant { defense: 100, ..fighter(0, 0, 0) };
+        for _ in 0..50 {
+            let roll = roll_attack(&mut rng, &attacker, &defender);
+            assert!(!roll.hit || roll.damage == 1);
+        }
+    }
+
+    #[test]
+    fn damage_stays_within_spread() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let attacker = fighter(200, 0, 0);
+        let defender = Combatant { defense: 0, ..fighter(0, 0, 0) };
+        for _ in 0..50 {
+            let roll = roll_attack(&mut rng, &attacker, &defender);
+            let (low, high) = if roll.crit { (20, 24) } else { (10, 12) };
+            assert!(!roll.hit || (low..=high).contains(&roll.damage));
+            assert!(roll.hit || roll.damage == 0);
+        }
+    }
+
+    #[test]
+    fn attack_descriptions() {
+        assert_eq!(describe_attack("Hero", "Goblin", &AttackRoll { hit: false, crit: false, damage: 0 }), "Hero attacks Goblin but misses.");
+        assert_eq!(describe_attack("Hero", "Goblin", &AttackRoll { hit: true, crit: true, damage: 8 }), "Hero critically hits Goblin for 8 damage!");
+        assert_eq!(describe_attack("Hero", "Goblin", &AttackRoll { hit: true, crit: false, damage: 4 }), "Hero hits Goblin for 4 damage.");
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::Map;
+use crate::layers::Layer;
+use crate::portals::Portal;
+use crate::zones::Zone;
+
+const MAGIC: &[u8; 4] = b"KMAP";
+const VERSION: u8 = 1;
+const MAX_DIMENSION: u32 = 4096;
+
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+    #[serde(default)]
+    portals: Vec<Portal>,
+    #[serde(default)]
+    zone: Zone,
+    #[serde(default)]
+    layers: Vec<Layer>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len()).ok_or_else(|| "Map data is truncated".to_string())?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+pub fn encode(map: &Map) -> Result<Vec<u8>, String> {
+    let (width, height) = (map.width as usize, map.height as usize);
+    if map.chunk_size.is_some() {
+        return Err("Chunked map manifests are stored as JSON; encode each chunk instead".to_string());
+    }
+    if map.width > MAX_DIMENSION || map.height > MAX_DIMENSION {
+        return Err(format!("Map dimensions {}x{} exceed {}", map.width, map.height, MAX_DIMENSION));
+    }
+    if map.tiles.len() != height || map.tiles.iter().any(|r| r.len() != width) || map.collisions.len() != height || map.collisions.iter().any(|r| r.len() != width) {
+        return Err("Map grids do not match its dimensions".to_string());
+    }
+
+    let mut palette: Vec<&String> = Vec::new();
+    let mut runs: Vec<(u16, u32)> = Vec::new();
+    for tile in map.tiles.iter().flatten() {
+        let index = match palette.iter().position(|t| *t == tile) {
+            Some(index) => index,
+            None => {
+                palette.push(tile);
+                palette.len() - 1
+            }
+        };
+        let index = u16::try_from(index).map_err(|_| "Map has too many tile types".to_string())?;
+        match runs.last_mut() {
+            Some((last, count)) if *last == index => *count += 1,
+            _ => runs.push((index, 1)),
+        }
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&map.width.to_le_bytes());
+    bytes.extend_from_slice(&map.height.to_le_bytes());
+    let palette_len = u16::try_from(palette.len()).map_err(|_| "Map has too many tile types".to_string())?;
+    bytes.extend_from_slice(&palette_len.to_le_bytes());
+    for tile in &palette {
+        let name = u8::try_from(tile.len()).map_err(|_| format!("Tile type name {} is too long", tile))?;
+        bytes.push(name);
+        bytes.extend_from_slice(tile.as_bytes());
+    }
+    bytes.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+    for (index, count) in runs {
+        bytes.extend_from_slice(&index.to_le_bytes());
+        bytes.extend_from_slice(&count.to_le_bytes());
+    }
+
+    let mut bits = vec![0u8; (width * height).div_ceil(8)];
+    for (i, &blocked) in map.collisions.iter().flatten().enumerate() {
+        if blocked {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+    }
+    bytes.extend_from_slice(&bits);
+
+    let metadata = serde_json::to_vec(&Metadata { portals: map.portals.clone(), zone: map.zone.clone(), layers: map.layers.clone() }).map_err(|e| e.to_string())?;
+    bytes.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&metadata);
+    Ok(bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Map, String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("Not a compact map".to_string());
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(format!("Unsupported map format version {}", version));
+    }
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(format!("Map dimensions {}x{} exceed {}", width, height, MAX_DIMENSION));
+    }
+
+    let palette_len = reader.u16()?;
+    let mut palette = Vec::with_capacity(palette_len as usize);
+    for _ in 0..palette_len {
+        let len = reader.u8()? as usize;
+        let name = String::from_utf8(reader.take(len)?.to_vec()).map_err(|_| "Tile type name is not valid UTF-8".to_string())?;
+        palette.push(name);
+    }
+
+    let cells = width as usize * height as usize;
+    let mut indices: Vec<u16> = Vec::with_capacity(cells);
+    for _ in 0..reader.u32()? {
+        let index = reader.u16()?;
+        let count = reader.u32()? as usize;
+        if index as usize >= palette.len() {
+            return Err(format!("Tile index {} is outside the palette", index));
+        }
+        if indices.len() + count > cells {
+            return Err("Tile runs exceed the map size".to_string());
+        }
+        indices.resize(indices.len() + count, index);
+    }
+    if indices.len() != cells {
+        return Err("Tile runs do not cover the map".to_string());
+    }
+
+    let bits = reader.take(cells.div_ceil(8))?;
+    let metadata_len = reader.u32()? as usize;
+    let metadata: Metadata = serde_json::from_slice(reader.take(metadata_len)?).map_err(|e| e.to_string())?;
+
+    let (width_cells, height_cells) = (width as usize, height as usize);
+    let tiles = (0..height_cells)
+        .map(|y| (0..width_cells).map(|x| palette[indices[y * width_cells + x] as usize].clone()).collect())
+        .collect();
+    let collisions = (0..height_cells)
+        .map(|y| (0..width_cells).map(|x| {
+            let i = y * width_cells + x;
+            bits[i / 8] & (1 << (i % 8)) != 0
+        }).collect())
+        .collect();
+    Ok(Map {
+        width,
+        height,
+        tiles,
+        collisions,
+        portals: metadata.portals,
+        zone: metadata.zone,
+        layers: metadata.layers,
+        layer_collisions: HashSet::new(),
+        chunk_size: None,
+        chunks: HashMap::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Map {
+        Map {
+            width: 3,
+            height: 2,
+            tiles: vec![
+                vec!["grass".to_string(), "grass".to_string(), "water".to_string()],
+                vec!["stone".to_string(), "grass".to_string(), "grass".to_string()],
+            ],
+            collisions: vec![vec![false, false, true], vec![true, false, false]],
+            portals: serde_json::from_str(r#"[{ "x": 1, "y": 1, "destination": "Town", "arrival_x": 5, "arrival_y": 5 }]"#).unwrap(),
+            zone: Zone { safe: true, ..Zone::default() },
+            layers: Vec::new(),
+            layer_collisions: HashSet::new(),
+            chunk_size: None,
+            chunks: HashMap::new(),
+        }
+    }
+
+    fn raw(version: u8, (width, height): (u32, u32), runs: &[(u16, u32)]) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(version);
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.push(5);
+        bytes.extend_from_slice(b"grass");
+        bytes.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+        for (index, count) in runs {
+            bytes.extend_from_slice(&index.to_le_bytes());
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes.extend(vec![0u8; (width as usize * height as usize).div_ceil(8)]);
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(b"{}");
+        bytes
+    }
+
+    #[test]
+    fn round_trip_preserves_map() {
+        let map = sample();
+        let decoded = decode(&encode(&map).unwrap()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (3, 2));
+        assert_eq!(decoded.tiles, map.tiles);
+        assert_eq!(decoded.collisions, map.collisions);
+        assert_eq!(decoded.portals.len(), 1);
+        assert_eq!(decoded.portals[0].destination, "Town");
+        assert!(decoded.zone.safe);
+        assert!(decoded.chunk_size.is_none());
+    }
+
+    #[test]
+    fn raw_sample_decodes() {
+        let decoded = decode(&raw(VERSION, (2, 2), &[(0, 4)])).unwrap();
+        assert_eq!(decoded.tiles, vec![vec!["grass".to_string(); 2]; 2]);
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let bytes = encode(&sample()).unwrap();
+        for len in 0..bytes.len() {
+            assert!(decode(&bytes[..len]).is_err(), "prefix of {} bytes decoded", len);
+        }
+    }
+
+    #[test]
+    fn palette_index_out_of_range_is_rejected() {
+        let error = decode(&raw(VERSION, (2, 2), &[(1, 4)])).err().unwrap();
+        assert_eq!(error, "Tile index 1 is outside the palette");
+    }
+
+    #[test]
+    fn overrunning_runs_are_rejected() {
+        let error = decode(&raw(VERSION, (2, 2), &[(0, 3), (0, 2)])).err().unwrap();
+        assert_eq!(error, "Tile runs exceed the map size");
+    }
+
+    #[test]
+    fn undershooting_runs_are_rejected() {
+        let error = decode(&raw(VERSION, (2, 2), &[(0, 3)])).err().unwrap();
+        assert_eq!(error, "Tile runs do not cover the map");
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let error = decode(&raw(VERSION + 1, (2, 2), &[(0, 4)])).err().unwrap();
+        assert_eq!(error, format!("Unsupported map format version {}", VERSION + 1));
+    }
+}
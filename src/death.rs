@@ -0,0 +1,216 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::Character;
+use crate::progression::Progression;
+use crate::zones::Zone;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RespawnPoint {
+    pub location: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeathRules {
+    #[serde(default = "default_experience_loss_percent")]
+    pub experience_loss_percent: u32,
+    #[serde(default = "default_durability_loss_percent")]
+    pub durability_loss_percent: u32,
+    #[serde(default = "default_gold_drop_percent")]
+    pub pvp_zone_gold_drop_percent: u32,
+    #[serde(default = "default_respawn_health_percent")]
+    pub respawn_health_percent: u32,
+    #[serde(default)]
+    pub respawn_effect: Option<String>,
+    #[serde(default = "default_respawn_point")]
+    pub default_respawn: RespawnPoint,
+    #[serde(default)]
+    pub respawn_points: HashMap<String, RespawnPoint>,
+    #[serde(default = "default_resurrection_cost")]
+    pub resurrection_cost_per_level: u64,
+    #[serde(default = "default_experience_restore_percent")]
+    pub experience_restore_percent: u32,
+    #[serde(default = "default_resurrection_range")]
+    pub resurrection_range: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DeathRecord {
+    pub location: String,
+    pub x: f32,
+    pub y: f32,
+    pub tick: u64,
+    pub experience_lost: u64,
+    pub gold_dropped: u64,
+}
+
+fn default_experience_loss_percent() -> u32 {
+    10
+}
+
+fn default_durability_loss_percent() -> u32 {
+    10
+}
+
+fn default_gold_drop_percent() -> u32 {
+    20
+}
+
+fn default_respawn_health_percent() -> u32 {
+    25
+}
+
+fn default_respawn_point() -> RespawnPoint {
+    RespawnPoint { location: "Town".to_string(), x: 5.0, y: 5.0 }
+}
+
+fn default_resurrection_cost() -> u64 {
+    10
+}
+
+fn default_experience_restore_percent() -> u32 {
+    50
+}
+
+fn default_resurrection_range() -> f32 {
+    2.0
+}
+
+impl Default for DeathRules {
+    fn default() -> Self {
+        DeathRules {
+            experience_loss_percent: default_experience_loss_percent(),
+            durability_loss_percent: default_durability_loss_percent(),
+            pvp_zone_gold_drop_percent: default_gold_drop_percent(),
+            respawn_health_percent: default_respawn_health_percent(),
+            respawn_effect: None,
+            default_respawn: default_respawn_point(),
+            respawn_points: HashMap::new(),
+            resurrection_cost_per_level: default_resurrection_cost(),
+            experience_restore_percent: default_experience_restore_percent(),
+            resurrection_range: default_resurrection_range(),
+        }
+    }
+}
+
+impl DeathRules {
+    pub fn respawn_point(&self, location: &str) -> &RespawnPoint {
+        self.respawn_points.get(location).unwrap_or(&self.default_respawn)
+    }
+}
+
+impl RespawnPoint {
+    pub fn distance_to(&self, x: f32, y: f32) -> f32 {
+        ((self.x - x).powi(2) + (self.y - y).powi(2)).sqrt()
+    }
+}
+
+pub fn apply_death(character: &mut Character, zone: &Zone, rules: &DeathRules, progression: &Progression, tick: u64, penalty_percent: u32) -> DeathRecord {
+    let floor = if character.level > 1 { progression.experience_for_level(character.level - 1) } else { 0 };
+    let progress = character.experience.saturating_sub(floor);
+    let experience_lost = (progress * (rules.experience_loss_percent * penalty_percent) as u64 / 10000).min(progress);
+    character.experience -= experience_lost;
+
+    for instance in character.equipment.slots_mut().into_iter().flatten() {
+        let loss = instance.max_durability * rules.durability_loss_percent * penalty_percent / 10000;
+        instance.wear(loss);
+    }
+
+    let gold_dropped = if zone.pvp {
+        (character.gold * (rules.pvp_zone_gold_drop_percent * penalty_percent) as u64 / 10000).min(character.gold)
+    } else {
+        0
+    };
+    character.gold -= gold_dropped;
+    character.health = 0;
+    character.mana = 0;
+    character.effects.clear();
+
+    let record = DeathRecord {
+        location: character.location.clone(),
+        x: character.x,
+        y: character.y,
+        tick,
+        experience_lost,
+        gold_dropped,
+    };
+    character.dead = true;
+    character.last_death = Some(record.clone());
+    record
+}
+
+pub fn describe_death(record: &DeathRecord) -> String {
+    let mut losses = vec![format!("{} EXP", record.experience_lost)];
+    if record.gold_dropped > 0 {
+        losses.push(format!("{} gold", record.gold_dropped));
+    }
+    format!("You have died and lost {}. Your equipment has been damaged.", losses.join(" and "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn character() -> Character {
+        let mut game = Game::new();
+        game.create_character("Hero".to_string(), "Elf".to_string(), "Warrior".to_string(), "hero-wallet".to_string()).unwrap();
+        game.character.unwrap()
+    }
+
+    fn die_in(zone: Zone) -> (Character, DeathRecord) {
+        let mut character = character();
+        let record = apply_death(&mut character, &zone, &DeathRules::default(), &Progression::default(), 7, 100);
+        (character, record)
+    }
+
+    #[test]
+    fn safe_zone_keeps_gold() {
+        let (character, record) = die_in(Zone { safe: true, ..Zone::default() });
+        assert_eq!(record.gold_dropped, 0);
+        assert_eq!(character.gold, 100);
+    }
+
+    #[test]
+    fn pvp_zone_drops_gold() {
+        let (character, record) = die_in(Zone { pvp: true, ..Zone::default() });
+        assert_eq!(record.gold_dropped, 20);
+        assert_eq!(character.gold, 80);
+    }
+
+    #[test]
+    fn unsafe_non_pvp_zone_keeps_gold() {
+        let (character, record) = die_in(Zone::default());
+        assert_eq!(record.gold_dropped, 0);
+        assert_eq!(character.gold, 100);
+    }
+
+    #[test]
+    fn penalty_scales_gold_drop() {
+        let mut character = character();
+        let record = apply_death(&mut character, &Zone { pvp: true, ..Zone::default() }, &DeathRules::default(), &Progression::default(), 7, 200);
+        assert_eq!(record.gold_dropped, 40);
+    }
+
+    #[test]
+    fn death_marks_character_and_records_it() {
+        let (character, record) = die_in(Zone::default());
+        assert!(character.dead);
+        assert_eq!((character.health, character.mana), (0, 0));
+        assert_eq!(record.tick, 7);
+        assert_eq!(character.last_death.unwrap().location, "Town");
+    }
+
+    #[test]
+    fn experience_loss_stays_within_current_level() {
+        let mut character = character();
+        let progression = Progression::default();
+        character.level = 2;
+        character.experience = progression.experience_for_level(1) + 50;
+        let record = apply_death(&mut character, &Zone::default(), &DeathRules::default(), &progression, 0, 100);
+        assert_eq!(record.experience_lost, 5);
+        assert_eq!(character.experience, progression.experience_for_level(1) + 45);
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Serialize, Deserialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DuelRules {
+    #[serde(default = "default_request_ticks")]
+    pub request_ticks: u64,
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: u32,
+    #[serde(default = "default_range")]
+    pub range: f32,
+    #[serde(default = "default_starting_rating")]
+    pub starting_rating: u32,
+    #[serde(default = "default_k_factor")]
+    pub k_factor: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArenaRecord {
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DuelRequest {
+    pub challenger_wallet: String,
+    pub challenger_name: String,
+    pub target_wallet: String,
+    pub ranked: bool,
+    pub rating: u32,
+    #[serde(default)]
+    pub received_tick: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DuelResult {
+    pub challenger_wallet: String,
+    pub challenger_name: String,
+    pub target_wallet: String,
+    pub target_name: String,
+    pub ranked: bool,
+    pub winner: Option<String>,
+    pub rounds: u32,
+    pub challenger_rating: u32,
+    pub target_rating: u32,
+    pub log: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LeaderboardEntry {
+    pub wallet: String,
+    pub name: String,
+    pub level: u32,
+    pub rating: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+fn default_request_ticks() -> u64 {
+    60
+}
+
+fn default_max_rounds() -> u32 {
+    30
+}
+
+fn default_range() -> f32 {
+    3.0
+}
+
+fn default_starting_rating() -> u32 {
+    1200
+}
+
+fn default_k_factor() -> f64 {
+    32.0
+}
+
+impl Default for DuelRules {
+    fn default() -> Self {
+        DuelRules {
+            request_ticks: default_request_ticks(),
+            max_rounds: default_max_rounds(),
+            range: default_range(),
+            starting_rating: default_starting_rating(),
+            k_factor: default_k_factor(),
+        }
+    }
+}
+
+impl ArenaRecord {
+    pub fn new(rating: u32) -> ArenaRecord {
+        ArenaRecord { rating, wins: 0, losses: 0, draws: 0 }
+    }
+}
+
+impl DuelResult {
+    pub fn answers(&self, request: &DuelRequest) -> bool {
+        self.challenger_wallet == request.challenger_wallet
+            && self.target_wallet == request.target_wallet
+            && self.ranked == request.ranked
+            && self.winner.iter().all(|w| *w == self.challenger_wallet || *w == self.target_wallet)
+    }
+}
+
+pub fn expected_score(rating: u32, opponent_rating: u32) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent_rating as f64 - rating as f64) / 400.0))
+}
+
+pub fn record_match(record: &mut ArenaRecord, opponent_rating: u32, score: f64, k_factor: f64) -> i32 {
+    match score {
+        s if s >= 1.0 => record.wins += 1,
+        s if s <= 0.0 => record.losses += 1,
+        _ => record.draws += 1,
+    }
+    let delta = (k_factor * (score - expected_score(record.rating, opponent_rating))).round() as i32;
+    record.rating = (record.rating as i64 + delta as i64).max(0) as u32;
+    delta
+}
+
+pub fn describe_rating_change(record: &ArenaRecord, delta: i32) -> String {
+    format!("Arena rating {} ({:+}). Record: {}W/{}L/{}D.", record.rating, delta, record.wins, record.losses, record.draws)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> DuelRequest {
+        DuelRequest {
+            challenger_wallet: "alice".to_string(),
+            challenger_name: "Alice".to_string(),
+            target_wallet: "bob".to_string(),
+            ranked: true,
+            rating: 1200,
+            received_tick: 0,
+        }
+    }
+
+    fn result() -> DuelResult {
+        DuelResult {
+            challenger_wallet: "alice".to_string(),
+            challenger_name: "Alice".to_string(),
+            target_wallet: "bob".to_string(),
+            target_name: "Bob".to_string(),
+            ranked: true,
+            winner: Some("bob".to_string()),
+            rounds: 4,
+            challenger_rating: 1200,
+            target_rating: 1200,
+            log: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn even_ratings_expect_half() {
+        assert!((expected_score(1200, 1200) - 0.5).abs() < 1e-9);
+        assert!((expected_score(1600, 1200) + expected_score(1200, 1600) - 1.0).abs() < 1e-9);
+        assert!(expected_score(1600, 1200) > 0.9);
+    }
+
+    #[test]
+    fn win_against_equal_gains_half_k() {
+        let mut record = ArenaRecord::new(1200);
+        assert_eq!(record_match(&mut record, 1200, 1.0, 32.0), 16);
+        assert_eq!((record.rating, record.wins), (1216, 1));
+    }
+
+    #[test]
+    fn loss_and_draw_are_recorded() {
+        let mut record = ArenaRecord::new(1200);
+        assert_eq!(record_match(&mut record, 1200, 0.0, 32.0), -16);
+        assert_eq!(record_match(&mut record, 1184, 0.5, 32.0), 0);
+        assert_eq!((record.rating, record.losses, record.draws), (1184, 1, 1));
+    }
+
+    #[test]
+    fn rating_never_goes_negative() {
+        let mut record = ArenaRecord::new(5);
+        record_match(&mut record, 5, 0.0, 32.0);
+        assert_eq!(record.rating, 0);
+    }
+
+    #[test]
+    fn result_answers_matching_request() {
+        assert!(result().answers(&request()));
+        assert!(DuelResult { winner: None, ..result() }.answers(&request()));
+    }
+
+    #[test]
+    fn forged_results_are_rejected() {
+        assert!(!DuelResult { challenger_wallet: "mallory".to_string(), ..result() }.answers(&request()));
+        assert!(!DuelResult { target_wallet: "carol".to_string(), ..result() }.answers(&request()));
+        assert!(!DuelResult { ranked: false, ..result() }.answers(&request()));
+        assert!(!DuelResult { winner: Some("mallory".to_string()), ..result() }.answers(&request()));
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationUnit {
+    #[default]
+    Turns,
+    Ticks,
+    Permanent,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StackRule {
+    #[default]
+    Refresh,
+    Stack,
+    Extend,
+    Ignore,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StatusEffect {
+    pub id: String,
+    pub name: String,
+    pub duration: u32,
+    #[serde(default)]
+    pub unit: DurationUnit,
+    #[serde(default)]
+    pub stacking: StackRule,
+    #[serde(default = "default_stacks")]
+    pub stacks: u32,
+    #[serde(default = "default_stacks")]
+    pub max_stacks: u32,
+    #[serde(default)]
+    pub tick_damage: u32,
+    #[serde(default)]
+    pub tick_heal: u32,
+    #[serde(default)]
+    pub stun: bool,
+    #[serde(default)]
+    pub modifiers: HashMap<String, i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct TickResult {
+    pub damage: u32,
+    pub heal: u32,
+}
+
+fn default_stacks() -> u32 {
+    1
+}
+
+pub fn apply_effect(effects: &mut Vec<StatusEffect>, definition: &StatusEffect) {
+    let Some(existing) = effects.iter_mut().find(|e| e.id == definition.id) else {
+        let mut effect = definition.clone();
+        effect.stacks = 1;
+        effects.push(effect);
+        return;
+    };
+
+    match definition.stacking {
+        StackRule::Refresh => existing.duration = definition.duration,
+        StackRule::Stack => {
+            existing.stacks = (existing.stacks + 1).min(definition.max_stacks.max(1));
+            existing.duration = definition.duration;
+        }
+        StackRule::Extend => existing.duration += definition.duration,
+        StackRule::Ignore => {}
+    }
+}
+
+pub fn remove_effect(effects: &mut Vec<StatusEffect>, id: &str) -> bool {
+    let before = effects.len();
+    effects.retain(|e| e.id != id);
+    effects.len() != before
+}
+
+pub fn tick_effects(effects: &mut Vec<StatusEffect>, unit: DurationUnit) -> TickResult {
+    let mut result = TickResult::default();
+    for effect in effects.iter_mut().filter(|e| e.unit == unit) {
+        result.damage += effect.tick_damage * effect.stacks;
+        result.heal += effect.tick_heal * effect.stacks;
+        effect.duration = effect.duration.saturating_sub(1);
+    }
+    effects.retain(|e| e.unit == DurationUnit::Permanent || e.duration > 0);
+    result
+}
+
+pub fn stat_modifier(effects: &[StatusEffect], stat: &str) -> i32 {
+    effects.iter().map(|e| e.modifiers.get(stat).copied().unwrap_or(0) * e.stacks as i32).sum()
+}
+
+pub fn modified(base: u32, effects: &[StatusEffect], stat: &str) -> u32 {
+    (base as i64 + stat_modifier(effects, stat) as i64).max(0) as u32
+}
+
+pub fn is_stunned(effects: &[StatusEffect]) -> bool {
+    effects.iter().any(|e| e.stun)
+}
@@ -0,0 +1,130 @@
+use serde::{Serialize, Deserialize};
+use rand::prelude::*;
+
+use crate::{InventoryItem, Item};
+
+pub const REPAIR_RANGE: f32 = 2.0;
+const AFFIX_STATS: [&str; 7] = ["strength", "endurance", "agility", "accuracy", "luck", "intellect", "wisdom"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Affix {
+    pub stat: String,
+    pub value: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ItemInstance {
+    pub instance_id: u32,
+    pub item_id: u32,
+    pub rarity: Rarity,
+    pub affixes: Vec<Affix>,
+    pub durability: u32,
+    pub max_durability: u32,
+}
+
+pub fn default_durability() -> u32 {
+    100
+}
+
+impl Rarity {
+    pub fn tier(self) -> u32 {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Uncommon => 1,
+            Rarity::Rare => 2,
+            Rarity::Epic => 3,
+            Rarity::Legendary => 4,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rarity::Common => "Common",
+            Rarity::Uncommon => "Uncommon",
+            Rarity::Rare => "Rare",
+            Rarity::Epic => "Epic",
+            Rarity::Legendary => "Legendary",
+        }
+    }
+}
+
+impl ItemInstance {
+    pub fn plain(instance_id: u32, item: &Item) -> ItemInstance {
+        ItemInstance {
+            instance_id,
+            item_id: item.id,
+            rarity: Rarity::Common,
+            affixes: Vec::new(),
+            durability: item.durability,
+            max_durability: item.durability,
+        }
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.max_durability > 0 && self.durability == 0
+    }
+
+    pub fn wear(&mut self, amount: u32) {
+        self.durability = self.durability.saturating_sub(amount);
+    }
+}
+
+pub fn is_equippable(item: &Item) -> bool {
+    match item.slot.as_str() {
+        "weapon" | "shield" => item.type_ == item.slot,
+        "armor" | "helmet" | "gloves" | "legs" | "boots" => item.type_ == "armor",
+        "amulet" | "ring" => item.type_ == "jewelry",
+        _ => false,
+    }
+}
+
+pub fn roll_rarity(rng: &mut impl Rng, luck: u32) -> Rarity {
+    let bonus = luck.min(50);
+    let weights = [(Rarity::Common, 600u32.saturating_sub(bonus * 6)), (Rarity::Uncommon, 250 + bonus * 2), (Rarity::Rare, 100 + bonus * 2), (Rarity::Epic, 40 + bonus), (Rarity::Legendary, 10 + bonus)];
+    weights.choose_weighted(rng, |w| w.1).map(|w| w.0).unwrap_or(Rarity::Common)
+}
+
+pub fn roll_instance(rng: &mut impl Rng, instance_id: u32, item: &Item, luck: u32) -> ItemInstance {
+    let rarity = roll_rarity(rng, luck);
+    let tier = rarity.tier();
+    let mut pool: Vec<&str> = item.stats.keys().map(|s| s.as_str()).chain(AFFIX_STATS).collect();
+    pool.sort();
+    pool.dedup();
+
+    let affixes = pool.choose_multiple(rng, tier as usize).map(|stat| Affix {
+        stat: stat.to_string(),
+        value: rng.gen_range(1..=3) * (tier + 1),
+    }).collect();
+
+    let max_durability = item.durability + item.durability * tier / 10;
+    ItemInstance {
+        instance_id,
+        item_id: item.id,
+        rarity,
+        affixes,
+        durability: max_durability,
+        max_durability,
+    }
+}
+
+pub fn take_instance(inventory: &mut Vec<InventoryItem>, item_id: u32) -> Option<ItemInstance> {
+    let idx = inventory.iter().position(|i| i.item_id == item_id && i.instance.is_some())?;
+    inventory.remove(idx).instance
+}
+
+pub fn repair_cost(item: &Item, instance: &ItemInstance) -> u64 {
+    if instance.max_durability == 0 {
+        return 0;
+    }
+    let missing = (instance.max_durability - instance.durability) as u64;
+    (missing * item.value as u64 / instance.max_durability as u64 / 2).max(if missing > 0 { 1 } else { 0 })
+}
@@ -0,0 +1,112 @@
+use serde::{Serialize, Deserialize};
+
+use crate::Character;
+use crate::death::RespawnPoint;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KarmaRules {
+    #[serde(default = "default_attack_penalty")]
+    pub attack_penalty: i32,
+    #[serde(default = "default_kill_penalty")]
+    pub kill_penalty: i32,
+    #[serde(default = "default_outlaw_kill_reward")]
+    pub outlaw_kill_reward: i32,
+    #[serde(default = "default_outlaw_threshold")]
+    pub outlaw_threshold: i32,
+    #[serde(default = "default_max_karma")]
+    pub max_karma: i32,
+    #[serde(default = "default_hostile_ticks")]
+    pub hostile_ticks: u64,
+    #[serde(default = "default_recovery_interval")]
+    pub recovery_interval_ticks: u64,
+    #[serde(default = "default_outlaw_penalty_percent")]
+    pub outlaw_penalty_percent: u32,
+    #[serde(default = "default_guard_damage")]
+    pub guard_damage: u32,
+    #[serde(default = "default_outlaw_respawn")]
+    pub outlaw_respawn: RespawnPoint,
+}
+
+fn default_attack_penalty() -> i32 {
+    5
+}
+
+fn default_kill_penalty() -> i32 {
+    25
+}
+
+fn default_outlaw_kill_reward() -> i32 {
+    10
+}
+
+fn default_outlaw_threshold() -> i32 {
+    -50
+}
+
+fn default_max_karma() -> i32 {
+    100
+}
+
+fn default_hostile_ticks() -> u64 {
+    120
+}
+
+fn default_recovery_interval() -> u64 {
+    60
+}
+
+fn default_outlaw_penalty_percent() -> u32 {
+    200
+}
+
+fn default_guard_damage() -> u32 {
+    5
+}
+
+fn default_outlaw_respawn() -> RespawnPoint {
+    RespawnPoint { location: "Wilderness".to_string(), x: 5.0, y: 5.0 }
+}
+
+impl Default for KarmaRules {
+    fn default() -> Self {
+        KarmaRules {
+            attack_penalty: default_attack_penalty(),
+            kill_penalty: default_kill_penalty(),
+            outlaw_kill_reward: default_outlaw_kill_reward(),
+            outlaw_threshold: default_outlaw_threshold(),
+            max_karma: default_max_karma(),
+            hostile_ticks: default_hostile_ticks(),
+            recovery_interval_ticks: default_recovery_interval(),
+            outlaw_penalty_percent: default_outlaw_penalty_percent(),
+            guard_damage: default_guard_damage(),
+            outlaw_respawn: default_outlaw_respawn(),
+        }
+    }
+}
+
+impl KarmaRules {
+    pub fn is_outlaw(&self, karma: i32) -> bool {
+        karma <= self.outlaw_threshold
+    }
+
+    pub fn penalty_percent(&self, karma: i32) -> u32 {
+        if self.is_outlaw(karma) { self.outlaw_penalty_percent } else { 100 }
+    }
+}
+
+pub fn adjust_karma(character: &mut Character, delta: i32, rules: &KarmaRules) -> Option<String> {
+    let was_outlaw = rules.is_outlaw(character.karma);
+    character.karma = (character.karma + delta).min(rules.max_karma);
+    match (was_outlaw, rules.is_outlaw(character.karma)) {
+        (false, true) => Some("You are now an outlaw! Guards will attack you in safe zones.".to_string()),
+        (true, false) => Some("You are no longer an outlaw.".to_string()),
+        _ => None,
+    }
+}
+
+pub fn recover_karma(character: &mut Character, tick: u64, rules: &KarmaRules) -> Option<String> {
+    if character.karma >= 0 || tick.checked_rem(rules.recovery_interval_ticks) != Some(0) {
+        return None;
+    }
+    adjust_karma(character, 1, rules)
+}
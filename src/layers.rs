@@ -0,0 +1,97 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+
+use crate::Map;
+use crate::tiles::{self, TileType};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerKind {
+    Decoration,
+    Objects,
+    Roof,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub kind: LayerKind,
+    #[serde(default)]
+    pub collides: bool,
+    #[serde(default)]
+    pub placements: Vec<Placement>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Placement {
+    pub x: u32,
+    pub y: u32,
+    pub tile: String,
+}
+
+impl Placement {
+    pub fn footprint(&self, tile_types: &[TileType]) -> [u32; 2] {
+        tiles::find(tile_types, &self.tile).map_or([1, 1], |t| t.footprint)
+    }
+
+    pub fn cells(&self, tile_types: &[TileType]) -> Vec<(u32, u32)> {
+        let [width, height] = self.footprint(tile_types);
+        (self.y..self.y.saturating_add(height)).flat_map(|y| (self.x..self.x.saturating_add(width)).map(move |x| (x, y))).collect()
+    }
+
+    pub fn covers(&self, tile_types: &[TileType], x: u32, y: u32) -> bool {
+        let [width, height] = self.footprint(tile_types);
+        (self.x..self.x.saturating_add(width)).contains(&x) && (self.y..self.y.saturating_add(height)).contains(&y)
+    }
+}
+
+pub fn tiles_at<'a>(map: &'a Map, tile_types: &[TileType], x: usize, y: usize) -> Vec<&'a String> {
+    let (x, y) = (x as u32, y as u32);
+    let mut result: Vec<&String> = map.layers.iter().rev()
+        .flat_map(|l| &l.placements)
+        .filter(|p| p.covers(tile_types, x, y))
+        .map(|p| &p.tile)
+        .collect();
+    result.extend(map.tile(x as usize, y as usize));
+    result
+}
+
+pub fn collision_cells(map: &Map, tile_types: &[TileType]) -> HashSet<(u32, u32)> {
+    let mut cells = HashSet::new();
+    for layer in map.layers.iter().filter(|l| l.collides) {
+        for placement in &layer.placements {
+            if !tiles::find(tile_types, &placement.tile).is_some_and(|t| t.walkable) {
+                cells.extend(placement.cells(tile_types));
+            }
+        }
+    }
+    cells
+}
+
+pub fn hidden_roof_cells(map: &Map, tile_types: &[TileType], x: f32, y: f32) -> HashSet<(u32, u32)> {
+    let roofs: HashSet<(u32, u32)> = map.layers.iter()
+        .filter(|l| l.kind == LayerKind::Roof)
+        .flat_map(|l| &l.placements)
+        .flat_map(|p| p.cells(tile_types))
+        .collect();
+    if map.zone.indoors {
+        return roofs;
+    }
+
+    let start = (x.max(0.0) as u32, y.max(0.0) as u32);
+    let mut hidden = HashSet::new();
+    if !roofs.contains(&start) {
+        return hidden;
+    }
+    let mut frontier = vec![start];
+    hidden.insert(start);
+    while let Some((cx, cy)) = frontier.pop() {
+        let neighbors = [(cx.wrapping_sub(1), cy), (cx + 1, cy), (cx, cy.wrapping_sub(1)), (cx, cy + 1)];
+        for next in neighbors {
+            if roofs.contains(&next) && hidden.insert(next) {
+                frontier.push(next);
+            }
+        }
+    }
+    hidden
+}
@@ -5,6 +5,51 @@ use serde::{Serialize, Deserialize};
 use rand::prelude::*;
 use js_sys::Array;
 
+mod ai;
+mod boss;
+mod chunks;
+mod combat;
+mod compact;
+mod death;
+mod duel;
+mod effects;
+mod items;
+mod karma;
+mod layers;
+mod loot;
+mod movement;
+mod pathfinding;
+mod portals;
+mod progression;
+mod pvp;
+mod skills;
+mod spawns;
+mod stats;
+mod tiles;
+mod validation;
+mod zones;
+
+use death::{DeathRecord, DeathRules, RespawnPoint};
+use duel::{ArenaRecord, DuelRequest, DuelResult, DuelRules, LeaderboardEntry};
+use ai::{AiAction, AiProfile, AiState};
+use boss::{BossKill, BossProfile};
+use chunks::{Chunk, ChunkRequest};
+use combat::{Combatant, Encounter, EncounterState};
+use effects::{DurationUnit, StatusEffect};
+use items::ItemInstance;
+use karma::KarmaRules;
+use layers::{Layer, LayerKind};
+use loot::{LootDrop, LootTable};
+use pathfinding::{Movement, Terrain};
+use portals::Portal;
+use progression::Progression;
+use pvp::{CombatProfile, PvpLoss, PvpResult, PvpRules};
+use spawns::{MonsterInstance, PendingRespawn, SpawnArea};
+use stats::StatSheet;
+use tiles::TileType;
+use validation::MapProblem;
+use zones::Zone;
+
 #[derive(Serialize, Deserialize, Clone)]
 pub enum Race {
     Human,
@@ -13,7 +58,7 @@ pub enum Race {
     Orc,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub enum Profession {
     Warrior,
     Mage,
@@ -23,21 +68,23 @@ pub enum Profession {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Equipment {
-    pub armor: Option<String>,
-    pub helmet: Option<String>,
-    pub amulet: Option<String>,
-    pub gloves: Option<String>,
-    pub ring: Option<String>,
-    pub weapon: Option<String>,
-    pub shield: Option<String>,
-    pub legs: Option<String>,
-    pub boots: Option<String>,
+    pub armor: Option<ItemInstance>,
+    pub helmet: Option<ItemInstance>,
+    pub amulet: Option<ItemInstance>,
+    pub gloves: Option<ItemInstance>,
+    pub ring: Option<ItemInstance>,
+    pub weapon: Option<ItemInstance>,
+    pub shield: Option<ItemInstance>,
+    pub legs: Option<ItemInstance>,
+    pub boots: Option<ItemInstance>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct InventoryItem {
     pub item_id: u32,
     pub quantity: u32,
+    #[serde(default)]
+    pub instance: Option<ItemInstance>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -48,16 +95,27 @@ pub struct SkillNode {
     pub skill: String,
     pub connections: Vec<u32>,
     pub unlocked: bool,
+    #[serde(default)]
+    pub effect: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Item {
     pub id: u32,
     pub name: String,
+    #[serde(rename = "type")]
     pub type_: String,
     pub slot: String,
     pub stats: std::collections::HashMap<String, u32>,
     pub value: u32,
+    #[serde(default)]
+    pub effects: Vec<String>,
+    #[serde(default = "items::default_durability")]
+    pub durability: u32,
+    #[serde(default)]
+    pub skill: Option<String>,
+    #[serde(default)]
+    pub level: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -68,14 +126,65 @@ pub struct Monster {
     pub stats: std::collections::HashMap<String, u32>,
     pub exp_reward: u32,
     pub gold_reward: [u32; 2],
+    #[serde(default)]
+    pub loot_table: Option<u32>,
+    #[serde(default)]
+    pub ai: AiProfile,
+    #[serde(default)]
+    pub boss: Option<BossProfile>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Npc {
+    pub id: u32,
+    pub name: String,
+    pub location: String,
+    pub x: f32,
+    pub y: f32,
+    pub role: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AbilityEffect {
+    Damage { value: u32 },
+    Heal { value: u32 },
+    Status,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ability {
+    pub id: u32,
+    pub name: String,
+    pub profession: Profession,
+    pub mana_cost: u32,
+    pub effect: AbilityEffect,
+    #[serde(default)]
+    pub applies: Vec<String>,
+    #[serde(default)]
+    pub school: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Map {
     pub width: u32,
     pub height: u32,
+    #[serde(default)]
     pub tiles: Vec<Vec<String>>,
+    #[serde(default)]
     pub collisions: Vec<Vec<bool>>,
+    #[serde(default)]
+    pub portals: Vec<Portal>,
+    #[serde(default)]
+    pub zone: Zone,
+    #[serde(default)]
+    pub layers: Vec<Layer>,
+    #[serde(skip)]
+    pub layer_collisions: std::collections::HashSet<(u32, u32)>,
+    #[serde(default)]
+    pub chunk_size: Option<u32>,
+    #[serde(skip)]
+    pub chunks: std::collections::HashMap<(u32, u32), Chunk>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -87,6 +196,18 @@ pub struct Player {
     pub y: f32,
     pub location: String,
     pub equipment: Equipment,
+    #[serde(default)]
+    pub combat: Option<CombatProfile>,
+    #[serde(default)]
+    pub defeated: bool,
+    #[serde(default)]
+    pub karma: i32,
+    #[serde(default)]
+    pub outlaw: bool,
+    #[serde(default)]
+    pub hostile: bool,
+    #[serde(default)]
+    pub arena: Option<ArenaRecord>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -99,6 +220,8 @@ pub struct Character {
     pub gold: u64,
     pub guild: Option<String>,
     pub skill_points: u32,
+    pub health: u32,
+    pub mana: u32,
     pub strength: u32,
     pub endurance: u32,
     pub wisdom: u32,
@@ -122,20 +245,54 @@ pub struct Character {
     pub alchemy: u32,
     pub equipment: Equipment,
     pub inventory: Vec<InventoryItem>,
+    pub next_instance_id: u32,
     pub skill_tree: Vec<SkillNode>,
+    pub effects: Vec<StatusEffect>,
     pub x: f32,
     pub y: f32,
     pub location: String,
+    pub defeated_until: Option<u64>,
+    pub dead: bool,
+    pub last_death: Option<DeathRecord>,
+    pub karma: i32,
+    pub hostile_until: Option<u64>,
+    pub arena: ArenaRecord,
+    pub boss_lockouts: std::collections::HashMap<u32, u64>,
 }
 
 #[wasm_bindgen]
 pub struct Game {
     character: Option<Character>,
+    wallet: Option<String>,
+    ticks: u64,
     rng: ThreadRng,
     other_players: Vec<Player>,
     items: Vec<Item>,
     monsters: Vec<Monster>,
+    abilities: Vec<Ability>,
+    status_effects: Vec<StatusEffect>,
+    tiles: Vec<TileType>,
+    npcs: Vec<Npc>,
+    loot_tables: Vec<LootTable>,
+    progression: Progression,
+    pvp_rules: PvpRules,
+    death_rules: DeathRules,
+    karma_rules: KarmaRules,
+    duel_rules: DuelRules,
+    outgoing_duels: Vec<DuelRequest>,
+    incoming_duels: Vec<DuelRequest>,
+    last_duel_result: Option<DuelResult>,
+    spawns: Vec<SpawnArea>,
+    monster_instances: Vec<MonsterInstance>,
+    pending_respawns: Vec<PendingRespawn>,
+    next_monster_instance_id: u32,
+    last_boss_kill: Option<BossKill>,
+    movement: Option<Movement>,
+    move_budget: f32,
+    pvp_cooldowns: std::collections::HashMap<String, u64>,
+    last_pvp_result: Option<PvpResult>,
     maps: std::collections::HashMap<String, Map>,
+    encounter: Option<Encounter>,
 }
 
 #[wasm_bindgen]
@@ -144,11 +301,36 @@ impl Game {
     pub fn new() -> Game {
         Game {
             character: None,
+            wallet: None,
+            ticks: 0,
             rng: thread_rng(),
             other_players: Vec::new(),
             items: Vec::new(),
             monsters: Vec::new(),
+            abilities: Vec::new(),
+            status_effects: Vec::new(),
+            tiles: Vec::new(),
+            npcs: Vec::new(),
+            loot_tables: Vec::new(),
+            progression: Progression::default(),
+            pvp_rules: PvpRules::default(),
+            death_rules: DeathRules::default(),
+            karma_rules: KarmaRules::default(),
+            duel_rules: DuelRules::default(),
+            outgoing_duels: Vec::new(),
+            incoming_duels: Vec::new(),
+            last_duel_result: None,
+            spawns: Vec::new(),
+            monster_instances: Vec::new(),
+            pending_respawns: Vec::new(),
+            next_monster_instance_id: 1,
+            last_boss_kill: None,
+            movement: None,
+            move_budget: 0.0,
+            pvp_cooldowns: std::collections::HashMap::new(),
+            last_pvp_result: None,
             maps: std::collections::HashMap::new(),
+            encounter: None,
         }
     }
 
@@ -156,15 +338,209 @@ impl Game {
         let data: serde_json::Value = game_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
         self.items = serde_json::from_value(data["items"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
         self.monsters = serde_json::from_value(data["monsters"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.abilities = serde_json::from_value(data["abilities"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.status_effects = serde_json::from_value(data["status_effects"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.tiles = serde_json::from_value(data["tiles"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        tiles::validate(&self.tiles).map_err(|e| JsValue::from_str(&e))?;
+        if let Some(tile) = self.tiles.iter().find(|t| t.resource.is_some_and(|id| !self.items.iter().any(|i| i.id == id))) {
+            return Err(JsValue::from_str(&format!("Tile type {} yields unknown item {}", tile.id, tile.resource.unwrap())));
+        }
+        self.npcs = serde_json::from_value(data["npcs"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.loot_tables = serde_json::from_value(data["loot_tables"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.progression = serde_json::from_value(data["progression"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.pvp_rules = serde_json::from_value(data["pvp"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.death_rules = serde_json::from_value(data["death"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.karma_rules = serde_json::from_value(data["karma"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.duel_rules = serde_json::from_value(data["duels"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        for monster in &self.monsters {
+            monster.ai.validate(&monster.name).map_err(|e| JsValue::from_str(&e))?;
+            if let Some(boss) = &monster.boss {
+                boss.validate(&monster.name).map_err(|e| JsValue::from_str(&e))?;
+                if let Some(id) = boss.phases.iter().flat_map(|p| &p.abilities).flat_map(|a| &a.applies).find(|id| !self.status_effects.iter().any(|e| &e.id == *id)) {
+                    return Err(JsValue::from_str(&format!("Boss {} references unknown status effect {}", monster.name, id)));
+                }
+            }
+        }
+        self.spawns = serde_json::from_value(data["spawns"].clone()).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        for area in &self.spawns {
+            if let Some(id) = area.monsters.iter().find(|id| !self.monsters.iter().any(|m| m.id == **id)) {
+                return Err(JsValue::from_str(&format!("Spawn area {} references unknown monster {}", area.id, id)));
+            }
+        }
+        if let Some(id) = self.death_rules.respawn_effect.as_ref().filter(|id| !self.status_effects.iter().any(|e| &e.id == *id)) {
+            return Err(JsValue::from_str(&format!("Death rules reference unknown status effect {}", id)));
+        }
+        for table in &self.loot_tables {
+            if let Some(entry) = table.entries.iter().chain(&table.guaranteed).find(|e| !self.items.iter().any(|i| i.id == e.item_id)) {
+                return Err(JsValue::from_str(&format!("Loot table {} references unknown item {}", table.id, entry.item_id)));
+            }
+        }
         Ok(())
     }
 
     pub fn load_map(&mut self, location: String, map_data: JsValue) -> Result<(), JsValue> {
-        let map: Map = map_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
-        self.maps.insert(location, map);
+        let map: Map = if map_data.is_instance_of::<js_sys::Uint8Array>() {
+            compact::decode(&js_sys::Uint8Array::new(&map_data).to_vec()).map_err(|e| JsValue::from_str(&e))?
+        } else {
+            map_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?
+        };
+        self.insert_map(&location, map).map_err(|e| JsValue::from_str(&e))
+    }
+
+    fn insert_map(&mut self, location: &str, mut map: Map) -> Result<(), String> {
+        map.layer_collisions = layers::collision_cells(&map, &self.tiles);
+        let mut problems = validation::validate(location, &map, &self.tiles);
+        if problems.is_empty() {
+            problems = self.world_problems(location, &map);
+        }
+        if !problems.is_empty() {
+            return Err(validation::describe_problems(location, &problems));
+        }
+        self.maps.insert(location.to_string(), map);
+        self.populate_spawns(location);
+        Ok(())
+    }
+
+    fn world_problems(&self, location: &str, map: &Map) -> Vec<MapProblem> {
+        let mut problems = Vec::new();
+        let other_zones = self.maps.iter().filter(|(l, _)| l.as_str() != location).map(|(_, m)| &m.zone);
+        let mut respawn_points = self.death_rules.respawn_points.values()
+            .chain(std::iter::once(&self.death_rules.default_respawn))
+            .chain(other_zones.filter_map(|z| z.respawn.as_ref()))
+            .chain(map.zone.respawn.as_ref());
+        if !map.zone.safe && respawn_points.any(|p| p.location == location) {
+            problems.push(MapProblem::UnsafeRespawn { location: location.to_string() });
+        }
+        if let Some(point) = map.zone.respawn.as_ref().filter(|p| p.location != location) {
+            if self.maps.get(&point.location).is_some_and(|m| !m.zone.safe) {
+                problems.push(MapProblem::UnsafeRespawn { location: point.location.clone() });
+            }
+        }
+        if self.karma_rules.outlaw_respawn.location == location && map.zone.safe {
+            problems.push(MapProblem::SafeOutlawRespawn);
+        }
+
+        let terrain = Terrain::walker(map, &self.tiles);
+        for area in self.spawns.iter().filter(|a| a.location == location) {
+            if map.zone.safe {
+                problems.push(MapProblem::SpawnInSafeZone { spawn_id: area.id });
+            }
+            let walkable = (area.y..area.y.saturating_add(area.height))
+                .flat_map(|y| (area.x..area.x.saturating_add(area.width)).map(move |x| (x as usize, y as usize)))
+                .any(|(x, y)| pathfinding::is_walkable(&terrain, x, y));
+            if !walkable && map.chunk_size.is_none() {
+                problems.push(MapProblem::SpawnAreaBlocked { spawn_id: area.id });
+            }
+            if let Some(monster) = area.monsters.iter().filter_map(|id| self.monsters.iter().find(|m| m.id == *id)).find(|m| !map.zone.allows_level(m.level)) {
+                problems.push(MapProblem::SpawnLevelOutOfRange { spawn_id: area.id, monster: monster.name.clone(), level: monster.level });
+            }
+        }
+
+        for portal in self.maps.values().flat_map(|m| &m.portals).filter(|p| p.destination == location) {
+            problems.extend(portals::check_arrival(portal, &Terrain::swimmer(map, &self.tiles)).err());
+        }
+        for portal in &map.portals {
+            if let Some(destination) = self.maps.get(&portal.destination).or((portal.destination == location).then_some(map)) {
+                problems.extend(portals::check_arrival(portal, &Terrain::swimmer(destination, &self.tiles)).err());
+            }
+        }
+        problems
+    }
+
+    fn populate_spawns(&mut self, location: &str) {
+        let Some(map) = self.maps.get(location) else {
+            return;
+        };
+        for area in self.spawns.iter().filter(|a| a.location == location) {
+            let alive = self.monster_instances.iter().filter(|m| m.spawn_id == area.id).count() as u32;
+            let pending = self.pending_respawns.iter().filter(|p| p.spawn_id == area.id).count() as u32;
+            for _ in (alive + pending)..area.max_population {
+                if let Some(instance) = spawns::spawn_instance(&mut self.rng, self.next_monster_instance_id, area, &self.monsters, &Terrain::walker(map, &self.tiles)) {
+                    self.next_monster_instance_id += 1;
+                    self.monster_instances.push(instance);
+                }
+            }
+        }
+    }
+
+    pub fn load_chunk(&mut self, location: String, x: u32, y: u32, chunk_data: JsValue) -> Result<(), JsValue> {
+        let chunk: Chunk = if chunk_data.is_instance_of::<js_sys::Uint8Array>() {
+            let data = compact::decode(&js_sys::Uint8Array::new(&chunk_data).to_vec()).map_err(|e| JsValue::from_str(&e))?;
+            Chunk { tiles: data.tiles, collisions: data.collisions }
+        } else {
+            chunk_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?
+        };
+        self.insert_chunk(&location, (x, y), chunk).map_err(|e| JsValue::from_str(&e))
+    }
+
+    fn insert_chunk(&mut self, location: &str, (x, y): (u32, u32), chunk: Chunk) -> Result<(), String> {
+        let map = self.maps.get_mut(location).ok_or("Map not loaded")?;
+        let problems = validation::validate_chunk(map, (x, y), &chunk, &self.tiles);
+        if !problems.is_empty() {
+            return Err(validation::describe_problems(location, &problems));
+        }
+        map.chunks.insert((x, y), chunk);
+        self.populate_spawns(location);
         Ok(())
     }
 
+    fn wanted_chunks(&self) -> Vec<ChunkRequest> {
+        let Some(character) = self.character.as_ref() else {
+            return Vec::new();
+        };
+        let Some(map) = self.maps.get(&character.location) else {
+            return Vec::new();
+        };
+        let mut wanted: Vec<ChunkRequest> = chunks::chunks_around(map, character.x, character.y, chunks::LOAD_RADIUS)
+            .into_iter()
+            .map(|(x, y)| ChunkRequest { location: character.location.clone(), x, y })
+            .collect();
+        for portal in &map.portals {
+            if let Some(destination) = self.maps.get(&portal.destination) {
+                wanted.extend(chunks::chunks_around(destination, portal.arrival_x, portal.arrival_y, 0).into_iter().map(|(x, y)| ChunkRequest { location: portal.destination.clone(), x, y }));
+            }
+        }
+        wanted
+    }
+
+    pub fn get_missing_chunks(&self) -> Result<JsValue, JsValue> {
+        let missing: Vec<ChunkRequest> = self.wanted_chunks()
+            .into_iter()
+            .filter(|c| self.maps.get(&c.location).is_some_and(|m| !m.chunks.contains_key(&(c.x, c.y))))
+            .collect();
+        JsValue::from_serde(&missing).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn camera_origin(&self) -> Vec<f32> {
+        let origin = self.character.as_ref()
+            .and_then(|c| self.maps.get(&c.location).map(|m| chunks::camera_origin(m, c.x, c.y)))
+            .unwrap_or((0.0, 0.0));
+        vec![origin.0, origin.1]
+    }
+
+    fn unload_chunks(&mut self) {
+        let wanted = self.wanted_chunks();
+        let Some(character) = self.character.as_ref() else {
+            return;
+        };
+        for (location, map) in self.maps.iter_mut() {
+            let Some(size) = map.chunk_size.filter(|&s| s > 0) else {
+                continue;
+            };
+            let here = *location == character.location;
+            map.chunks.retain(|&(x, y), _| {
+                wanted.iter().any(|c| &c.location == location && c.x == x && c.y == y)
+                    || (here && chunks::within(size, (x, y), character.x, character.y, chunks::UNLOAD_RADIUS))
+            });
+        }
+    }
+
+    pub fn get_monsters(&self) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let nearby: Vec<&MonsterInstance> = self.monster_instances.iter().filter(|m| m.location == character.location).collect();
+        JsValue::from_serde(&nearby).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     pub fn create_character(&mut self, name: String, race: String, profession: String, wallet: String) -> Result<(), JsValue> {
         let race = match race.as_str() {
             "Human" => Race::Human,
@@ -182,43 +558,32 @@ impl Game {
             _ => return Err(JsValue::from_str("Invalid profession")),
         };
 
-        let (strength, endurance, wisdom, mystic, agility, accuracy, intellect, luck, equipment) = match (&race, &profession) {
-            (Race::Human, Profession::Warrior) => (15, 15, 5, 5, 10, 10, 5, 5, Equipment {
-                weapon: Some("Iron Sword".to_string()),
-                shield: Some("Steel Shield".to_string()),
-                ..Equipment::default()
-            }),
-            (Race::Elf, Profession::Mage) => (5, 5, 15, 15, 10, 5, 15, 5, Equipment {
-                weapon: Some("Magic Staff".to_string()),
-                ..Equipment::default()
-            }),
-            (Race::Orc, Profession::Archer) => (10, 10, 5, 5, 15, 15, 5, 5, Equipment {
-                weapon: Some("Bow".to_string()),
-                ..Equipment::default()
-            }),
-            (Race::Dwarf, Profession::Blacksmith) => (15, 15, 5, 5, 5, 5, 10, 10, Equipment {
-                weapon: Some("Blacksmith Hammer".to_string()),
-                ..Equipment::default()
-            }),
-            _ => (10, 10, 10, 10, 10, 10, 10, 10, Equipment::default()),
+        let (strength, endurance, wisdom, mystic, agility, accuracy, intellect, luck, starting_gear) = match (&race, &profession) {
+            (Race::Human, Profession::Warrior) => (15, 15, 5, 5, 10, 10, 5, 5, vec![1, 4]),
+            (Race::Elf, Profession::Mage) => (5, 5, 15, 15, 10, 5, 15, 5, vec![3]),
+            (Race::Orc, Profession::Archer) => (10, 10, 5, 5, 15, 15, 5, 5, vec![9]),
+            (Race::Dwarf, Profession::Blacksmith) => (15, 15, 5, 5, 5, 5, 10, 10, vec![10]),
+            _ => (10, 10, 10, 10, 10, 10, 10, 10, Vec::new()),
         };
 
         let skill_tree = vec![
-            SkillNode { id: 0, x: 0.0, y: 0.0, skill: "+5 Strength".to_string(), connections: vec![1, 2], unlocked: true },
-            SkillNode { id: 1, x: 50.0, y: 0.0, skill: "+5 Sword".to_string(), connections: vec![0, 3], unlocked: false },
-            SkillNode { id: 2, x: 0.0, y: 50.0, skill: "+5 Endurance".to_string(), connections: vec![0, 3], unlocked: false },
-            SkillNode { id: 3, x: 50.0, y: 50.0, skill: "+10 Health".to_string(), connections: vec![1, 2], unlocked: false },
+            SkillNode { id: 0, x: 0.0, y: 0.0, skill: "Origin".to_string(), connections: vec![1, 2], unlocked: true, effect: None },
+            SkillNode { id: 1, x: 50.0, y: 0.0, skill: "+5 Sword".to_string(), connections: vec![0, 3], unlocked: false, effect: None },
+            SkillNode { id: 2, x: 0.0, y: 50.0, skill: "+5 Endurance".to_string(), connections: vec![0, 3], unlocked: false, effect: None },
+            SkillNode { id: 3, x: 50.0, y: 50.0, skill: "+10 Health".to_string(), connections: vec![1, 2], unlocked: false, effect: None },
         ];
 
         self.character = Some(Character {
             name,
             race,
-            profession,
+            profession: profession.clone(),
             level: 1,
             experience: 0,
             gold: 100,
             guild: None,
             skill_points: 1,
+            health: 0,
+            mana: 0,
             strength,
             endurance,
             wisdom,
@@ -240,68 +605,258 @@ impl Game {
             mining: if profession == Profession::Blacksmith { 10 } else { 0 },
             fishing: 0,
             alchemy: 0,
-            equipment,
-            inventory: vec![InventoryItem { item_id: 1, quantity: 1 }, InventoryItem { item_id: 2, quantity: 1 }],
+            equipment: Equipment::default(),
+            inventory: Vec::new(),
+            next_instance_id: 1,
             skill_tree,
+            effects: Vec::new(),
             x: 5.0,
             y: 5.0,
             location: "Town".to_string(),
+            defeated_until: None,
+            dead: false,
+            last_death: None,
+            karma: 0,
+            hostile_until: None,
+            arena: ArenaRecord::new(self.duel_rules.starting_rating),
+            boss_lockouts: std::collections::HashMap::new(),
         });
+        self.add_to_inventory(1, 1)?;
+        self.add_to_inventory(2, 1)?;
+        for item_id in starting_gear {
+            self.add_to_inventory(item_id, 1)?;
+            self.equip_item(item_id)?;
+        }
+
+        let character = self.character.as_mut().unwrap();
+        let sheet = StatSheet::compute(character, &self.items);
+        character.health = sheet.max_health;
+        character.mana = sheet.max_mana;
 
         self.other_players.push(Player {
-            wallet,
-            name: self.character.as_ref().unwrap().name.clone(),
+            wallet: wallet.clone(),
+            name: character.name.clone(),
             level: 1,
             x: 5.0,
             y: 5.0,
             location: "Town".to_string(),
-            equipment,
+            equipment: character.equipment.clone(),
+            combat: Some(CombatProfile::new(&sheet, character.health)),
+            defeated: false,
+            karma: 0,
+            outlaw: false,
+            hostile: false,
+            arena: Some(character.arena.clone()),
         });
+        self.wallet = Some(wallet);
+        self.move_budget = movement::max_budget(movement::speed(sheet.stat("agility")));
 
         Ok(())
     }
 
     pub fn add_to_inventory(&mut self, item_id: u32, quantity: u32) -> Result<(), JsValue> {
         let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
-        if let Some(item) = character.inventory.iter_mut().find(|i| i.item_id == item_id) {
+        if let Some(item) = self.items.iter().find(|i| i.id == item_id && items::is_equippable(i)) {
+            for _ in 0..quantity {
+                let instance = ItemInstance::plain(character.next_instance_id, item);
+                character.next_instance_id += 1;
+                character.inventory.push(InventoryItem { item_id, quantity: 1, instance: Some(instance) });
+            }
+        } else if let Some(item) = character.inventory.iter_mut().find(|i| i.item_id == item_id && i.instance.is_none()) {
             item.quantity += quantity;
         } else {
-            character.inventory.push(InventoryItem { item_id, quantity });
+            character.inventory.push(InventoryItem { item_id, quantity, instance: None });
         }
         Ok(())
     }
 
     pub fn remove_from_inventory(&mut self, item_id: u32, quantity: u32) -> Result<(), JsValue> {
         let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
-        if let Some(item) = character.inventory.iter_mut().find(|i| i.item_id == item_id) {
-            if item.quantity >= quantity {
-                item.quantity -= quantity;
-                if item.quantity == 0 {
-                    character.inventory.retain(|i| i.item_id != item_id);
-                }
-                Ok(())
-            } else {
-                Err(JsValue::from_str("Not enough items"))
-            }
-        } else {
-            Err(JsValue::from_str("Item not in inventory"))
+        let owned: u32 = character.inventory.iter().filter(|i| i.item_id == item_id).map(|i| i.quantity).sum();
+        if owned == 0 {
+            return Err(JsValue::from_str("Item not in inventory"));
+        }
+        if owned < quantity {
+            return Err(JsValue::from_str("Not enough items"));
         }
+
+        let mut remaining = quantity;
+        for item in character.inventory.iter_mut().filter(|i| i.item_id == item_id) {
+            let taken = item.quantity.min(remaining);
+            item.quantity -= taken;
+            remaining -= taken;
+        }
+        character.inventory.retain(|i| i.quantity > 0);
+        Ok(())
     }
 
-    pub fn update_position(&mut self, x: f32, y: f32, location: String) -> Result<(), JsValue> {
+    pub fn repair_item(&mut self, instance_id: u32) -> Result<String, JsValue> {
         let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let is_blacksmith = character.profession == Profession::Blacksmith;
+        if !is_blacksmith && nearest_npc(&self.npcs, "blacksmith", &character.location, (character.x, character.y), items::REPAIR_RANGE).is_none() {
+            return Err(JsValue::from_str("Find a blacksmith in town to repair items"));
+        }
+
+        let instance = character.equipment.slots_mut().into_iter().flatten()
+            .chain(character.inventory.iter_mut().filter_map(|i| i.instance.as_mut()))
+            .find(|i| i.instance_id == instance_id)
+            .ok_or_else(|| JsValue::from_str("Item not found"))?;
+        let item = self.items.iter().find(|i| i.id == instance.item_id).ok_or_else(|| JsValue::from_str("Item not found"))?;
+
+        let cost = if is_blacksmith { 0 } else { items::repair_cost(item, instance) };
+        if character.gold < cost {
+            return Err(JsValue::from_str("Not enough gold"));
+        }
+
+        instance.durability = instance.max_durability;
+        character.gold -= cost;
+        Ok(format!("Repaired {} for {} gold.", item.name, cost))
+    }
+
+    pub fn update_position(&mut self, x: f32, y: f32) -> Result<String, JsValue> {
+        self.move_character(x, y).map_err(|e| JsValue::from_str(&e))
+    }
+
+    fn move_character(&mut self, x: f32, y: f32) -> Result<String, String> {
+        let character = self.character.as_mut().ok_or("No character created")?;
+        if character.dead {
+            return Err("You are dead".to_string());
+        }
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Err("Cannot move while in combat".to_string());
+        }
+        let map = self.maps.get(&character.location).ok_or("Map not loaded")?;
+
+        let speed = movement::speed(StatSheet::compute(character, &self.items).stat("agility"));
+        let from = (character.x, character.y);
+        let requested = movement::distance(from, (x, y));
+        if requested > movement::max_budget(speed) {
+            return Err("Teleport rejected: destination is too far away".to_string());
+        }
+        if requested > self.move_budget {
+            return Err("You cannot move that fast".to_string());
+        }
+
+        let (new_x, new_y) = movement::sweep(&Terrain::swimmer(map, &self.tiles), from, (x, y));
+        if requested > 0.0 && (new_x, new_y) == from {
+            return Err("Invalid or impassable position".to_string());
+        }
+        self.move_budget -= movement::distance(from, (new_x, new_y));
+        character.x = new_x;
+        character.y = new_y;
+        self.movement = None;
+        if pathfinding::tile_of(new_x, new_y) == pathfinding::tile_of(from.0, from.1) {
+            return Ok(String::new());
+        }
+        Ok(self.enter_portal().unwrap_or_default())
+    }
+
+    fn enter_portal(&mut self) -> Option<String> {
+        let character = self.character.as_mut()?;
+        let portal = portals::portal_at(self.maps.get(&character.location)?, character.x, character.y)?.clone();
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Some(format!("You cannot take {} while in combat.", portal.label()));
+        }
+        let Some(destination) = self.maps.get(&portal.destination) else {
+            return Some(format!("The way to {} is not open yet.", portal.label()));
+        };
+        if let Some(min_level) = destination.zone.min_level.filter(|&min| character.level < min) {
+            return Some(format!("You must be level {} to enter {}.", min_level, portal.label()));
+        }
+        if !destination.is_loaded(portal.arrival_x.max(0.0) as usize, portal.arrival_y.max(0.0) as usize) {
+            return Some(format!("The way to {} is not open yet.", portal.label()));
+        }
+        if let Err(problem) = portals::check_arrival(&portal, &Terrain::swimmer(destination, &self.tiles)) {
+            return Some(format!("The portal to {} is blocked: {}.", portal.label(), problem.describe()));
+        }
+        character.location = portal.destination.clone();
+        character.x = portal.arrival_x;
+        character.y = portal.arrival_y;
+        self.movement = None;
+        Some(format!("You travel to {}.", portal.label()))
+    }
+
+    pub fn get_zone(&self, location: String) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(zones::zone_of(&self.maps, &location)).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn describe_zone(&self, location: String) -> String {
+        zones::zone_of(&self.maps, &location).describe(&location)
+    }
+
+    pub fn get_portal_destinations(&self, location: String) -> Result<JsValue, JsValue> {
         let map = self.maps.get(&location).ok_or_else(|| JsValue::from_str("Map not loaded"))?;
+        let mut destinations: Vec<&String> = map.portals.iter().map(|p| &p.destination).collect();
+        destinations.sort();
+        destinations.dedup();
+        JsValue::from_serde(&destinations).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn find_path(&self, x: f32, y: f32) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let map = self.maps.get(&character.location).ok_or_else(|| JsValue::from_str("Map not loaded"))?;
+        let path = pathfinding::find_path(&Terrain::swimmer(map, &self.tiles), pathfinding::tile_of(character.x, character.y), pathfinding::tile_of(x, y)).ok_or_else(|| JsValue::from_str("No path to destination"))?;
+        JsValue::from_serde(&path).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
 
-        let tile_x = x.floor() as usize;
-        let tile_y = y.floor() as usize;
-        if tile_x >= map.width as usize || tile_y >= map.height as usize || map.collisions[tile_y][tile_x] {
-            return Err(JsValue::from_str("Invalid or impassable position"));
+    pub fn move_to(&mut self, x: f32, y: f32) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.dead {
+            return Err(JsValue::from_str("You are dead"));
+        }
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Err(JsValue::from_str("Cannot move while in combat"));
         }
+        let map = self.maps.get(&character.location).ok_or_else(|| JsValue::from_str("Map not loaded"))?;
+        let path = pathfinding::find_path(&Terrain::swimmer(map, &self.tiles), pathfinding::tile_of(character.x, character.y), pathfinding::tile_of(x, y)).ok_or_else(|| JsValue::from_str("No path to destination"))?;
+        self.movement = (!path.is_empty()).then(|| Movement { location: character.location.clone(), path: path.clone(), budget: 0 });
+        JsValue::from_serde(&path).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
 
-        character.x = x;
-        character.y = y;
-        character.location = location;
-        Ok(())
+    pub fn is_moving(&self) -> bool {
+        self.movement.is_some()
+    }
+
+    fn advance_movement(&mut self) -> Option<String> {
+        let (Some(movement), Some(character)) = (self.movement.as_mut(), self.character.as_mut()) else {
+            return None;
+        };
+        let in_combat = self.encounter.as_ref().is_some_and(|e| !e.is_over());
+        let Some(map) = self.maps.get(&movement.location).filter(|_| !character.dead && character.location == movement.location && !in_combat) else {
+            self.movement = None;
+            return None;
+        };
+        let terrain = Terrain::swimmer(map, &self.tiles);
+
+        let speed = movement::speed(StatSheet::compute(character, &self.items).stat("agility"));
+        movement.budget += (speed * pathfinding::BASE_COST as f32) as u32;
+        let mut stepped = false;
+        while let Some(&(x, y)) = movement.path.first() {
+            if !pathfinding::is_walkable(&terrain, x, y) {
+                movement.path.clear();
+                break;
+            }
+            let cost = pathfinding::step_cost(&terrain, x, y);
+            if movement.budget < cost {
+                break;
+            }
+            movement.budget -= cost;
+            character.x = x as f32;
+            character.y = y as f32;
+            movement.path.remove(0);
+            stepped = true;
+            if portals::portal_at(map, character.x, character.y).is_some() {
+                movement.path.clear();
+            }
+        }
+        if movement.path.is_empty() {
+            self.movement = None;
+        }
+        if stepped {
+            self.enter_portal()
+        } else {
+            None
+        }
     }
 
     pub fn update_other_players(&mut self, players: JsValue) -> Result<(), JsValue> {
@@ -310,182 +865,1136 @@ impl Game {
         Ok(())
     }
 
-    pub fn fight_monster(&mut self) -> Result<String, JsValue> {
+    pub fn fight_monster(&mut self, instance_id: Option<u32>) -> Result<String, JsValue> {
+        if self.encounter.as_ref().filter(|e| !e.is_over()).is_none() {
+            self.start_encounter(instance_id)?;
+        }
+
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let encounter = self.encounter.as_ref().unwrap();
+        let monster = self.monsters.iter().find(|m| m.id == encounter.monster_id).ok_or_else(|| JsValue::from_str("Monster not found"))?;
+
+        if effects::is_stunned(&character.effects) {
+            let message = format!("{} is stunned and cannot act.", character.name);
+            return self.resolve_round(0, vec![message]);
+        }
+
+        let player = Combatant::from_sheet(&StatSheet::compute(character, &self.items));
+        let enemy = Combatant::from_monster(monster, &encounter.monster_effects);
+        let roll = combat::roll_attack(&mut self.rng, &player, &enemy);
+        let mut messages = vec![combat::describe_attack(&character.name, &monster.name, &roll)];
+        if roll.hit {
+            if let Some(weapon) = character.equipment.weapon.as_mut() {
+                weapon.wear(1);
+            }
+            if let Some(skill) = weapon_skill(&character.equipment, &self.items) {
+                messages.extend(skills::try_improve(&mut self.rng, character, &skill, monster.level, &self.progression));
+            }
+        }
+        self.resolve_round(roll.damage, messages)
+    }
+
+    pub fn use_ability(&mut self, ability_id: u32, target: String) -> Result<String, JsValue> {
         let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
-        if self.monsters.is_empty() {
-            return Err(JsValue::from_str("No monsters loaded"));
+        if character.dead {
+            return Err(JsValue::from_str("You are dead"));
+        }
+        let ability = self.abilities.iter().find(|a| a.id == ability_id).ok_or_else(|| JsValue::from_str("Ability not found"))?;
+        if ability.profession != character.profession {
+            return Err(JsValue::from_str("Your profession cannot use this ability"));
+        }
+        if character.mana < ability.mana_cost {
+            return Err(JsValue::from_str("Not enough mana"));
+        }
+        if effects::is_stunned(&character.effects) {
+            return Err(JsValue::from_str("You are stunned"));
         }
 
-        let monster_idx = self.rng.gen_range(0..self.monsters.len());
-        let monster = &self.monsters[monster_idx];
-        let damage = (character.strength + character.accuracy) / 2;
-        let health = *monster.stats.get("health").unwrap_or(&0);
-        if damage >= health {
-            let exp_gain = monster.exp_reward;
-            let gold_gain = self.rng.gen_range(monster.gold_reward[0]..=monster.gold_reward[1]);
-            let skill_gain = self.rng.gen_range(1..5);
+        let in_combat = self.encounter.as_ref().is_some_and(|e| !e.is_over());
+        match (&ability.effect, target.as_str()) {
+            (AbilityEffect::Damage { .. }, "enemy") | (AbilityEffect::Status, "enemy") if !in_combat => return Err(JsValue::from_str("Not in combat")),
+            (AbilityEffect::Damage { .. }, "enemy") | (AbilityEffect::Heal { .. }, "self") | (AbilityEffect::Status, "enemy" | "self") => {}
+            _ => return Err(JsValue::from_str("Invalid target for this ability")),
+        }
 
-            character.experience += exp_gain as u64;
-            character.gold += gold_gain as u64;
-            character.skill_points += 1;
-            let item_drop = self.rng.gen_range(1..=4);
-            self.add_to_inventory(item_drop, 1)?;
+        let mut statuses = Vec::new();
+        for status_id in &ability.applies {
+            statuses.push(self.status_effects.iter().find(|e| &e.id == status_id).ok_or_else(|| JsValue::from_str("Status effect not found"))?);
+        }
 
-            let next_level_exp = (character.level * 100) as u64;
-            if character.experience >= next_level_exp {
-                character.level += 1;
-                character.strength += 2;
-                character.endurance += 2;
-                character.agility += 2;
+        character.mana -= ability.mana_cost;
+        let sheet = StatSheet::compute(character, &self.items);
+        let power = ability_power(&sheet, ability);
+        let mut message = match ability.effect {
+            AbilityEffect::Damage { value } => format!("{} casts {} for {} damage!", character.name, ability.name, value + power),
+            AbilityEffect::Heal { value } => {
+                let healed = (value + power).min(sheet.max_health.saturating_sub(character.health));
+                character.health += healed;
+                format!("{} casts {} and recovers {} HP.", character.name, ability.name, healed)
             }
+            AbilityEffect::Status => format!("{} casts {}.", character.name, ability.name),
+        };
 
-            match character.profession {
-                Profession::Warrior => character.sword += skill_gain,
-                Profession::Mage => character.magic += skill_gain,
-                Profession::Archer => character.bow += skill_gain,
-                Profession::Blacksmith => character.mining += skill_gain,
+        if !statuses.is_empty() {
+            let targets = if target == "self" { &mut character.effects } else { &mut self.encounter.as_mut().unwrap().monster_effects };
+            let names: Vec<&str> = statuses.iter().map(|e| e.name.as_str()).collect();
+            message.push_str(&format!(" Applied {}.", names.join(", ")));
+            for status in statuses {
+                effects::apply_effect(targets, status);
             }
+        }
 
-            Ok(format!("Defeated {} (Level {})! Gained {} EXP, {} gold, 1 skill point, and item {}.", monster.name, monster.level, exp_gain, gold_gain, item_drop))
+        let mut messages = vec![message];
+        let difficulty = self.encounter.as_ref().filter(|_| in_combat).map_or(character.level, |e| e.monster_level);
+        if let Some(skill) = ability.school.clone().or_else(|| weapon_skill(&character.equipment, &self.items)) {
+            messages.extend(skills::try_improve(&mut self.rng, character, &skill, difficulty, &self.progression));
+        }
+
+        let damage = match ability.effect {
+            AbilityEffect::Damage { value } => value + power,
+            _ => 0,
+        };
+        if in_combat {
+            self.resolve_round(damage, messages)
         } else {
-            Ok(format!("Attacked {} but it survived!", monster.name))
+            Ok(messages.join(" "))
         }
     }
 
-    pub fn fight_player(&mut self, target_wallet: String) -> Result<String, JsValue> {
+    pub fn gather(&mut self) -> Result<String, JsValue> {
         let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
-        if !is_pvp_zone(&character.location) {
-            return Err(JsValue::from_str("Cannot fight in a non-PvP zone"));
+        if character.dead {
+            return Err(JsValue::from_str("You are dead"));
+        }
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Err(JsValue::from_str("Cannot gather while in combat"));
         }
 
-        let target = self.other_players.iter().find(|p| p.wallet == target_wallet).ok_or_else(|| JsValue::from_str("Player not found"))?;
-        let damage = (character.strength + character.accuracy) / 2;
-        let exp_gain = target.level * 5;
-        character.experience += exp_gain as u64;
-        character.gold += self.rng.gen_range(20..100) as u64;
-
-        Ok(format!("Attacked {} (Level {})! Gained {} EXP and {} gold.", target.name, target.level, exp_gain, character.gold))
+        let map = self.maps.get(&character.location).ok_or_else(|| JsValue::from_str("Map not loaded"))?;
+        let (cx, cy) = (character.x.floor() as i64, character.y.floor() as i64);
+        let item = [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].iter()
+            .filter_map(|(dx, dy)| Some(layers::tiles_at(map, &self.tiles, usize::try_from(cx + dx).ok()?, usize::try_from(cy + dy).ok()?)))
+            .flatten()
+            .filter_map(|tile| tiles::find(&self.tiles, tile).and_then(|t| t.resource))
+            .find_map(|item_id| self.items.iter().find(|i| i.id == item_id && i.skill.is_some()))
+            .ok_or_else(|| JsValue::from_str("Nothing to gather here"))?;
+
+        let skill = item.skill.clone().unwrap();
+        let skill_value = StatSheet::compute(character, &self.items).stat(&skill);
+        let chance = (0.5 + (skill_value as f64 - (item.level * 10) as f64) / 50.0).clamp(0.1, 0.95);
+        let mut messages = Vec::new();
+        let success = self.rng.gen_bool(chance);
+        messages.extend(skills::try_improve(&mut self.rng, character, &skill, item.level, &self.progression));
+
+        let (item_id, name) = (item.id, item.name.clone());
+        if !success {
+            messages.insert(0, format!("You failed to gather {}.", name));
+            return Ok(messages.join(" "));
+        }
+        messages.insert(0, format!("You gathered 1 {}.", name));
+        self.add_to_inventory(item_id, 1)?;
+        Ok(messages.join(" "))
     }
 
-    pub fn unlock_skill_node(&mut self, node_id: u32) -> Result<(), JsValue> {
+    pub fn use_item(&mut self, item_id: u32) -> Result<String, JsValue> {
         let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
-        if character.skill_points == 0 {
-            return Err(JsValue::from_str("No skill points available"));
+        if character.dead {
+            return Err(JsValue::from_str("You are dead"));
         }
-
-        let node = character.skill_tree.iter_mut().find(|n| n.id == node_id).ok_or_else(|| JsValue::from_str("Node not found"))?;
-        if node.unlocked {
-            return Err(JsValue::from_str("Node already unlocked"));
+        let item = self.items.iter().find(|i| i.id == item_id).ok_or_else(|| JsValue::from_str("Item not found"))?;
+        if item.type_ != "potion" {
+            return Err(JsValue::from_str("Item cannot be used"));
+        }
+        if !character.inventory.iter().any(|i| i.item_id == item_id) {
+            return Err(JsValue::from_str("Item not in inventory"));
         }
 
-        let has_unlocked_neighbor = character.skill_tree.iter().any(|n| n.unlocked && n.connections.contains(&node_id));
-        if !has_unlocked_neighbor && node_id != 0 {
-            return Err(JsValue::from_str("Must unlock a connected node first"));
+        let mut statuses = Vec::new();
+        for status_id in &item.effects {
+            statuses.push(self.status_effects.iter().find(|e| &e.id == status_id).ok_or_else(|| JsValue::from_str("Status effect not found"))?);
         }
 
-        node.unlocked = true;
-        character.skill_points -= 1;
+        let sheet = StatSheet::compute(character, &self.items);
+        let healed = item.stats.get("health").copied().unwrap_or(0).min(sheet.max_health.saturating_sub(character.health));
+        let restored = item.stats.get("mana").copied().unwrap_or(0).min(sheet.max_mana.saturating_sub(character.mana));
+        character.health += healed;
+        character.mana += restored;
+        for status in statuses {
+            effects::apply_effect(&mut character.effects, status);
+        }
 
-        match node.skill.as_str() {
-            "+5 Strength" => character.strength += 5,
-            "+5 Sword" => character.sword += 5,
-            "+5 Endurance" => character.endurance += 5,
-            "+10 Health" => character.endurance += 10,
-            _ => {}
+        let message = format!("Used {}. Recovered {} HP and {} mana.", item.name, healed, restored);
+        self.remove_from_inventory(item_id, 1)?;
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            self.resolve_round(0, vec![message])
+        } else {
+            Ok(message)
         }
+    }
 
-        Ok(())
+    pub fn get_abilities(&self) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let abilities: Vec<&Ability> = self.abilities.iter().filter(|a| a.profession == character.profession).collect();
+        JsValue::from_serde(&abilities).map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
-    pub fn equip_item(&mut self, item_id: u32) -> Result<(), JsValue> {
-        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
-        let item = self.items.iter().find(|i| i.id == item_id).ok_or_else(|| JsValue::from_str("Item not found"))?;
-        
-        self.remove_from_inventory(item_id, 1)?;
-        
-        match item.slot.as_str() {
-            "armor" => character.equipment.armor = Some(item.name.clone()),
-            "helmet" => character.equipment.helmet = Some(item.name.clone()),
-            "amulet" => character.equipment.amulet = Some(item.name.clone()),
-            "gloves" => character.equipment.gloves = Some(item.name.clone()),
-            "ring" => character.equipment.ring = Some(item.name.clone()),
-            "weapon" => character.equipment.weapon = Some(item.name.clone()),
-            "shield" => character.equipment.shield = Some(item.name.clone()),
-            "legs" => character.equipment.legs = Some(item.name.clone()),
-            "boots" => character.equipment.boots = Some(item.name.clone()),
-            _ => return Err(JsValue::from_str("Invalid equipment slot")),
-        }
-
-        for (stat, value) in &item.stats {
-            match stat.as_str() {
-                "strength" => character.strength += value,
-                "endurance" => character.endurance += value,
-                "accuracy" => character.accuracy += value,
-                "magic" => character.magic += value,
-                "shield_skill" => character.shield_skill += value,
-                _ => {}
+    pub fn tick(&mut self) -> Result<String, JsValue> {
+        self.ticks += 1;
+        let ticks = self.ticks;
+        if self.pending_respawns.iter().any(|p| p.due_tick <= ticks) {
+            self.pending_respawns.retain(|p| p.due_tick > ticks);
+            let locations: Vec<String> = self.maps.keys().cloned().collect();
+            for location in locations {
+                self.populate_spawns(&location);
             }
         }
+        let expiry = self.duel_rules.request_ticks;
+        self.outgoing_duels.retain(|r| ticks < r.received_tick + expiry);
+        self.incoming_duels.retain(|r| ticks < r.received_tick + expiry);
+        let mut messages: Vec<String> = self.advance_movement().into_iter().collect();
+        self.unload_chunks();
+        messages.extend(self.update_monsters());
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.dead {
+            return Ok(messages.join(" "));
+        }
+        let mut tick = effects::tick_effects(&mut character.effects, DurationUnit::Ticks);
+        let sheet = StatSheet::compute(character, &self.items);
+        let speed = movement::speed(sheet.stat("agility"));
+        self.move_budget = (self.move_budget + speed).min(movement::max_budget(speed));
 
-        Ok(())
-    }
+        if character.hostile_until.is_some_and(|until| self.ticks >= until) {
+            character.hostile_until = None;
+        }
+        messages.extend(karma::recover_karma(character, self.ticks, &self.karma_rules));
+        if self.karma_rules.is_outlaw(character.karma) && zones::zone_of(&self.maps, &character.location).safe && character.defeated_until.is_none() {
+            tick.damage += self.karma_rules.guard_damage;
+            messages.push(format!("The {} guards attack you for {} damage!", character.location, self.karma_rules.guard_damage));
+        }
+        let (tile_x, tile_y) = pathfinding::tile_of(character.x, character.y);
+        let hazard = self.maps.get(&character.location).and_then(|m| Terrain::swimmer(m, &self.tiles).tile(tile_x, tile_y)).filter(|t| t.damage_per_tick > 0);
+        if let Some(tile) = hazard.filter(|_| character.defeated_until.is_none()) {
+            tick.damage += tile.damage_per_tick;
+            messages.push(format!("The {} burns you for {} damage!", tile.name.to_lowercase(), tile.damage_per_tick));
+        }
 
-    pub fn get_character(&self) -> Result<JsValue, JsValue> {
-        match &self.character {
-            Some(character) => JsValue::from_serde(character).map_err(|e| JsValue::from_str(&e.to_string())),
-            None => Err(JsValue::from_str("No character created")),
+        if character.defeated_until.is_some_and(|until| self.ticks >= until) {
+            character.defeated_until = None;
+            character.health = character.health.max(sheet.max_health / 2);
+            messages.push("You have recovered from your defeat.".to_string());
         }
-    }
 
-    pub fn get_inventory(&self) -> Result<JsValue, JsValue> {
-        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
-        JsValue::from_serde(&character.inventory).map_err(|e| JsValue::from_str(&e.to_string()))
+        if character.defeated_until.is_none() {
+            character.health = (character.health + tick.heal).min(sheet.max_health).saturating_sub(tick.damage);
+        }
+        if tick.damage > 0 || tick.heal > 0 {
+            messages.push(format!("Status effects: -{} HP, +{} HP.", tick.damage, tick.heal));
+        }
+        if character.health == 0 && character.defeated_until.is_none() {
+            if let Some(encounter) = self.encounter.as_mut().filter(|e| !e.is_over()) {
+                encounter.state = EncounterState::Died;
+            }
+            let record = death::apply_death(character, zones::zone_of(&self.maps, &character.location), &self.death_rules, &self.progression, self.ticks, self.karma_rules.penalty_percent(character.karma));
+            messages.push("You succumbed to your wounds.".to_string());
+            messages.push(death::describe_death(&record));
+        }
+        Ok(messages.join(" "))
     }
 
-    pub fn render_game(&self, canvas_id: String, show_skill_tree: bool) -> Result<(), JsValue> {
-        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
-        let document = web_sys::window().unwrap().document().unwrap();
-        let canvas = document.get_element_by_id(&canvas_id).ok_or_else(|| JsValue::from_str("Canvas not found"))?;
-        let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>().map_err(|_| JsValue::from_str("Not a canvas"))?;
-        let gl = canvas.get_context("webgl")?.ok_or_else(|| JsValue::from_str("WebGL not supported"))?.dyn_into::<GL>()?;
+    fn update_monsters(&mut self) -> Vec<String> {
+        let mut messages = Vec::new();
+        let target = self.character.as_ref()
+            .filter(|c| !c.dead && c.defeated_until.is_none() && !zones::zone_of(&self.maps, &c.location).safe)
+            .map(|c| (c.location.clone(), c.x, c.y));
+        let engaged = self.encounter.as_ref().filter(|e| !e.is_over()).map(|e| e.instance_id);
 
-        if show_skill_tree {
-            return self.render_skill_tree(canvas_id);
+        for instance in self.monster_instances.iter_mut() {
+            if Some(instance.instance_id) == engaged {
+                continue;
+            }
+            let (Some(map), Some(monster)) = (self.maps.get(&instance.location), self.monsters.iter().find(|m| m.id == instance.monster_id)) else {
+                continue;
+            };
+            let position = target.as_ref().filter(|t| t.0 == instance.location).map(|t| (t.1, t.2));
+            let action = ai::step(instance, &monster.ai, &Terrain::walker(map, &self.tiles), position, self.ticks);
+            if action == AiAction::Engage && self.encounter.as_ref().filter(|e| !e.is_over()).is_none() {
+                self.encounter = Some(Encounter::new(monster, instance));
+                messages.push(format!("{} attacks you!", instance.name));
+            }
         }
+        messages
+    }
 
-        let vert_shader = gl.create_shader(GL::VERTEX_SHADER).ok_or_else(|| JsValue::from_str("Unable to create shader"))?;
-        gl.shader_source(&vert_shader, r#"
-            attribute vec2 a_position;
-            uniform vec2 u_resolution;
-            void main() {
-                vec2 iso_pos = vec2(a_position.x - a_position.y, (a_position.x + a_position.y) * 0.5);
-                vec2 normalized = iso_pos / u_resolution * 2.0 - 1.0;
-                gl_Position = vec4(normalized, 0.0, 1.0);
-                gl_PointSize = 20.0;
+    fn resolve_round(&mut self, damage: u32, mut messages: Vec<String>) -> Result<String, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let encounter = self.encounter.as_mut().filter(|e| !e.is_over()).ok_or_else(|| JsValue::from_str("Not in combat"))?;
+        let monster = self.monsters.iter().find(|m| m.id == encounter.monster_id).ok_or_else(|| JsValue::from_str("Monster not found"))?;
+
+        encounter.round += 1;
+        encounter.monster_health = encounter.monster_health.saturating_sub(damage);
+        let sheet = StatSheet::compute(character, &self.items);
+        character.mana = (character.mana + 1 + sheet.stat("wisdom") / 10).min(sheet.max_mana);
+
+        let monster_stunned = effects::is_stunned(&encounter.monster_effects);
+        let monster_tick = effects::tick_effects(&mut encounter.monster_effects, DurationUnit::Turns);
+        encounter.monster_health = (encounter.monster_health + monster_tick.heal).min(encounter.monster_max_health).saturating_sub(monster_tick.damage);
+        if monster_tick.damage > 0 {
+            messages.push(format!("{} suffers {} damage from status effects.", monster.name, monster_tick.damage));
+        }
+        if let Some(instance) = self.monster_instances.iter_mut().find(|m| m.instance_id == encounter.instance_id) {
+            instance.health = encounter.monster_health;
+            if monster.ai.should_flee(encounter.monster_health, encounter.monster_max_health) && !monster_stunned {
+                encounter.state = EncounterState::MonsterFled;
+                instance.state = AiState::Fleeing;
+                character.effects.retain(|e| e.unit != DurationUnit::Turns);
+                messages.push(format!("{} flees from the fight!", monster.name));
+                encounter.log.extend(messages.iter().cloned());
+                return Ok(messages.join(" "));
             }
-        "#);
-        gl.compile_shader(&vert_shader);
+        }
 
-        let frag_shader = gl.create_shader(GL::FRAGMENT_SHADER).ok_or_else(|| JsValue::from_str("Unable to create shader"))?;
-        gl.shader_source(&frag_shader, r#"
-            precision mediump float;
-            uniform vec4 u_color;
-            void main() {
-                gl_FragColor = u_color;
+        if let Some(boss) = monster.boss.as_ref().filter(|_| encounter.monster_health > 0) {
+            let phase = boss.phase_for(encounter.monster_health, encounter.monster_max_health);
+            if phase > encounter.phase {
+                encounter.phase = phase;
+                let entered = &boss.phases[phase];
+                messages.push(entered.message.clone().unwrap_or_else(|| format!("{} enters {}!", monster.name, entered.name)));
             }
-        "#);
-        gl.compile_shader(&frag_shader);
+        }
 
-        let program = gl.create_program().ok_or_else(|| JsValue::from_str("Unable to create program"))?;
-        gl.attach_shader(&program, &vert_shader);
-        gl.attach_shader(&program, &frag_shader);
-        gl.link_program(&program);
-        gl.use_program(Some(&program));
+        if encounter.monster_health == 0 {
+            encounter.state = EncounterState::Won;
+            if let Some(idx) = self.monster_instances.iter().position(|m| m.instance_id == encounter.instance_id) {
+                let instance = self.monster_instances.remove(idx);
+                if let Some(area) = self.spawns.iter().find(|a| a.id == instance.spawn_id) {
+                    self.pending_respawns.push(PendingRespawn { spawn_id: area.id, due_tick: self.ticks + area.respawn_ticks });
+                }
+            }
+            character.effects.retain(|e| e.unit != DurationUnit::Turns);
+            let monster = monster.clone();
+            let mut exp_gain = monster.exp_reward;
+            let mut locked_out = None;
+            if let Some(boss) = &monster.boss {
+                let wallet = self.wallet.clone().unwrap_or_default();
+                let participants: Vec<String> = self.other_players.iter()
+                    .filter(|p| p.wallet != wallet && !p.defeated && p.location == character.location)
+                    .filter(|p| ((p.x - character.x).powi(2) + (p.y - character.y).powi(2)).sqrt() <= boss.credit_radius)
+                    .map(|p| p.wallet.clone())
+                    .collect();
+                exp_gain /= participants.len() as u32 + 1;
+                locked_out = boss::is_locked_out(&character.boss_lockouts, monster.id, self.ticks);
+                if locked_out.is_none() {
+                    character.boss_lockouts.insert(monster.id, self.ticks + boss.lockout_ticks);
+                }
+                if !participants.is_empty() {
+                    messages.push(format!("Kill credit is shared with {} other players.", participants.len()));
+                }
+                self.last_boss_kill = Some(BossKill {
+                    monster_id: monster.id,
+                    boss_name: monster.name.clone(),
+                    location: character.location.clone(),
+                    killer_wallet: wallet,
+                    killer_name: character.name.clone(),
+                    participants,
+                    experience_share: exp_gain,
+                });
+            }
+
+            let level_up = progression::grant_experience(character, exp_gain as u64, &self.progression);
+            let (gold_gain, drops) = match locked_out {
+                Some(_) => (0, Vec::new()),
+                None => self.roll_monster_rewards(&monster, sheet.stat("luck"))?,
+            };
+            messages.push(format!("Defeated {} (Level {})! Gained {} EXP, {} gold, and {}.", monster.name, monster.level, level_up.experience_gained, gold_gain, loot::describe_drops(&drops)));
+            if let Some(remaining) = locked_out {
+                messages.push(format!("You are locked out of {} loot for {} more ticks.", monster.name, remaining));
+            }
+            messages.extend(progression::describe_level_up(&level_up));
+            let encounter = self.encounter.as_mut().unwrap();
+            encounter.log.extend(messages.iter().cloned());
+            encounter.loot = drops;
+            return Ok(messages.join(" "));
+        }
+
+        if monster_stunned {
+            messages.push(format!("{} is stunned and cannot act.", monster.name));
+        } else {
+            let player = Combatant::from_sheet(&sheet);
+            let mut enemy = Combatant::from_monster(monster, &encounter.monster_effects);
+            if let Some(boss) = &monster.boss {
+                enemy.attack += boss.phases[encounter.phase].attack_bonus;
+                if boss.enrage_rounds.is_some_and(|rounds| encounter.round >= rounds) {
+                    if !encounter.enraged {
+                        encounter.enraged = true;
+                        messages.push(format!("{} becomes enraged!", monster.name));
+                    }
+                    enemy.attack = enemy.attack * boss.enrage_attack_percent / 100;
+                }
+            }
+            let counter = combat::roll_attack(&mut self.rng, &enemy, &player);
+            messages.push(combat::describe_attack(&monster.name, &character.name, &counter));
+            character.health = character.health.saturating_sub(counter.damage);
+            if counter.hit {
+                if let Some(piece) = character.equipment.armor_pieces_mut().choose_mut(&mut self.rng) {
+                    piece.wear(1);
+                }
+            }
+            if character.equipment.shield.is_some() {
+                messages.extend(skills::try_improve(&mut self.rng, character, "shield_skill", monster.level, &self.progression));
+            }
+
+            if let Some(boss) = &monster.boss {
+                for ability in boss.phases[encounter.phase].abilities.iter().filter(|a| encounter.round % a.every_rounds == 0) {
+                    match &ability.effect {
+                        AbilityEffect::Damage { value } => {
+                            character.health = character.health.saturating_sub(*value);
+                            messages.push(format!("{} uses {} for {} damage!", monster.name, ability.name, value));
+                        }
+                        AbilityEffect::Heal { value } => {
+                            encounter.monster_health = (encounter.monster_health + value).min(encounter.monster_max_health);
+                            messages.push(format!("{} uses {} and recovers {} HP!", monster.name, ability.name, value));
+                        }
+                        AbilityEffect::Status => messages.push(format!("{} uses {}!", monster.name, ability.name)),
+                    }
+                    for effect in ability.applies.iter().filter_map(|id| self.status_effects.iter().find(|e| &e.id == id)) {
+                        effects::apply_effect(&mut character.effects, effect);
+                    }
+                }
+            }
+        }
+
+        if character.health > 0 {
+            let tick = effects::tick_effects(&mut character.effects, DurationUnit::Turns);
+            character.health = (character.health + tick.heal).min(sheet.max_health).saturating_sub(tick.damage);
+            if tick.damage > 0 {
+                messages.push(format!("{} suffers {} damage from status effects.", character.name, tick.damage));
+            }
+        }
+
+        if character.health == 0 {
+            encounter.state = EncounterState::Died;
+            if let Some(instance) = self.monster_instances.iter_mut().find(|m| m.instance_id == encounter.instance_id) {
+                instance.state = AiState::Returning;
+            }
+            let record = death::apply_death(character, zones::zone_of(&self.maps, &character.location), &self.death_rules, &self.progression, self.ticks, self.karma_rules.penalty_percent(character.karma));
+            messages.push(format!("You were slain by {}.", monster.name));
+            messages.push(death::describe_death(&record));
+        } else {
+            messages.push(format!("{} HP: {}/{}. Your HP: {}/{}.", monster.name, encounter.monster_health, encounter.monster_max_health, character.health, sheet.max_health));
+        }
+
+        encounter.log.extend(messages.iter().cloned());
+        Ok(messages.join(" "))
+    }
+
+    fn roll_monster_rewards(&mut self, monster: &Monster, luck: u32) -> Result<(u32, Vec<LootDrop>), JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let gold = self.rng.gen_range(monster.gold_reward[0]..=monster.gold_reward[1]);
+        character.gold += gold as u64;
+        let rolled = match monster.loot_table.and_then(|id| self.loot_tables.iter().find(|t| t.id == id)) {
+            Some(table) => loot::roll_loot(&mut self.rng, table, character.level, luck),
+            None => Vec::new(),
+        };
+
+        let mut drops = Vec::new();
+        for (item_id, quantity) in rolled {
+            drops.extend(self.grant_loot(item_id, quantity)?);
+        }
+        Ok((gold, drops))
+    }
+
+    pub fn take_last_boss_kill(&mut self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.last_boss_kill.take()).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn receive_boss_credit(&mut self, kill: JsValue) -> Result<String, JsValue> {
+        let kill: BossKill = kill.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let wallet = self.wallet.clone().ok_or_else(|| JsValue::from_str("No wallet connected"))?;
+        if !kill.participants.contains(&wallet) {
+            return Err(JsValue::from_str("You did not take part in that kill"));
+        }
+        let monster = self.monsters.iter().find(|m| m.id == kill.monster_id).cloned().ok_or_else(|| JsValue::from_str("Monster not found"))?;
+        let boss = monster.boss.as_ref().ok_or_else(|| JsValue::from_str("Monster is not a boss"))?;
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.dead || character.location != kill.location {
+            return Err(JsValue::from_str("You are too far away to share the kill"));
+        }
+
+        let level_up = progression::grant_experience(character, kill.experience_share as u64, &self.progression);
+        let mut messages = vec![format!("{} defeated {}! You share the kill credit and gain {} EXP.", kill.killer_name, kill.boss_name, level_up.experience_gained)];
+        messages.extend(progression::describe_level_up(&level_up));
+        match boss::is_locked_out(&character.boss_lockouts, monster.id, self.ticks) {
+            Some(remaining) => messages.push(format!("You are locked out of {} loot for {} more ticks.", monster.name, remaining)),
+            None => {
+                character.boss_lockouts.insert(monster.id, self.ticks + boss.lockout_ticks);
+                let luck = StatSheet::compute(character, &self.items).stat("luck");
+                let (gold, drops) = self.roll_monster_rewards(&monster, luck)?;
+                messages.push(format!("Received {} gold and {}.", gold, loot::describe_drops(&drops)));
+            }
+        }
+        Ok(messages.join(" "))
+    }
+
+    fn grant_loot(&mut self, item_id: u32, quantity: u32) -> Result<Vec<LootDrop>, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let Some(item) = self.items.iter().find(|i| i.id == item_id) else {
+            return Err(JsValue::from_str("Loot table references an unknown item"));
+        };
+        if !items::is_equippable(item) {
+            let drop = LootDrop { item_id, name: item.name.clone(), quantity, instance_id: None, rarity: None };
+            self.add_to_inventory(item_id, quantity)?;
+            return Ok(vec![drop]);
+        }
+
+        let luck = StatSheet::compute(character, &self.items).stat("luck");
+        let mut drops = Vec::new();
+        for _ in 0..quantity {
+            let instance = items::roll_instance(&mut self.rng, character.next_instance_id, item, luck);
+            character.next_instance_id += 1;
+            drops.push(LootDrop { item_id, name: item.name.clone(), quantity: 1, instance_id: Some(instance.instance_id), rarity: Some(instance.rarity) });
+            character.inventory.push(InventoryItem { item_id, quantity: 1, instance: Some(instance) });
+        }
+        Ok(drops)
+    }
+
+    fn nearest_respawn(&self, location: &str, x: f32, y: f32) -> RespawnPoint {
+        let usable = |p: &&RespawnPoint| self.maps.get(&p.location).is_some_and(|m| m.zone.safe);
+        let local = self.maps.values().filter_map(|m| m.zone.respawn.as_ref())
+            .chain(self.death_rules.respawn_points.values())
+            .chain(std::iter::once(&self.death_rules.default_respawn))
+            .filter(|p| p.location == location)
+            .filter(usable)
+            .min_by(|a, b| a.distance_to(x, y).total_cmp(&b.distance_to(x, y)));
+        local.or_else(|| zones::zone_of(&self.maps, location).respawn.as_ref().filter(usable))
+            .or_else(|| Some(self.death_rules.respawn_point(location)).filter(usable))
+            .unwrap_or(&self.death_rules.default_respawn)
+            .clone()
+    }
+
+    pub fn respawn(&mut self) -> Result<String, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if !character.dead {
+            return Err(JsValue::from_str("You are not dead"));
+        }
+
+        let point = if self.karma_rules.is_outlaw(character.karma) {
+            self.karma_rules.outlaw_respawn.clone()
+        } else {
+            self.nearest_respawn(&character.location, character.x, character.y)
+        };
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let sheet = StatSheet::compute(character, &self.items);
+        character.dead = false;
+        character.location = point.location.clone();
+        character.x = point.x;
+        character.y = point.y;
+        character.health = (sheet.max_health * self.death_rules.respawn_health_percent / 100).max(1);
+        character.mana = sheet.max_mana * self.death_rules.respawn_health_percent / 100;
+        if let Some(effect) = self.death_rules.respawn_effect.as_ref().and_then(|id| self.status_effects.iter().find(|e| &e.id == id)) {
+            effects::apply_effect(&mut character.effects, effect);
+        }
+        self.encounter = None;
+        Ok(format!("You respawn in {}.", point.location))
+    }
+
+    pub fn temple_resurrect(&mut self) -> Result<String, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let priest = nearest_npc(&self.npcs, "priest", &character.location, (character.x, character.y), self.death_rules.resurrection_range)
+            .ok_or_else(|| JsValue::from_str("There is no priest close enough to perform the resurrection"))?;
+        if self.karma_rules.is_outlaw(character.karma) {
+            return Err(JsValue::from_str(&format!("{} refuses to serve outlaws", priest.name)));
+        }
+
+        let sheet = StatSheet::compute(character, &self.items);
+        let sickness = self.death_rules.respawn_effect.clone();
+        let sick = sickness.as_ref().is_some_and(|id| character.effects.iter().any(|e| &e.id == id));
+        if !character.dead && character.last_death.is_none() && !sick && character.health >= sheet.max_health {
+            return Err(JsValue::from_str(&format!("{} finds nothing to heal", priest.name)));
+        }
+        let cost = character.level as u64 * self.death_rules.resurrection_cost_per_level;
+        if character.gold < cost {
+            return Err(JsValue::from_str("Not enough gold for the resurrection service"));
+        }
+
+        character.gold -= cost;
+        let mut messages = if character.dead {
+            character.dead = false;
+            self.encounter = None;
+            vec![format!("{} resurrects you for {} gold.", priest.name, cost)]
+        } else {
+            vec![format!("{} restores you for {} gold.", priest.name, cost)]
+        };
+        character.health = sheet.max_health;
+        character.mana = sheet.max_mana;
+        if let Some(id) = sickness {
+            if effects::remove_effect(&mut character.effects, &id) {
+                messages.push("Your resurrection sickness is lifted.".to_string());
+            }
+        }
+        if let Some(record) = character.last_death.take() {
+            let restored = record.experience_lost * self.death_rules.experience_restore_percent as u64 / 100;
+            if restored > 0 {
+                character.experience += restored;
+                messages.push(format!("Recovered {} EXP lost on death.", restored));
+            }
+        }
+        Ok(messages.join(" "))
+    }
+
+    pub fn flee_encounter(&mut self) -> Result<String, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let encounter = self.encounter.as_mut().filter(|e| !e.is_over()).ok_or_else(|| JsValue::from_str("Not in combat"))?;
+        let monster = self.monsters.iter().find(|m| m.id == encounter.monster_id).ok_or_else(|| JsValue::from_str("Monster not found"))?;
+        if effects::is_stunned(&character.effects) {
+            return Err(JsValue::from_str("You are stunned"));
+        }
+
+        let player = Combatant::from_sheet(&StatSheet::compute(character, &self.items));
+        if self.rng.gen_bool(combat::flee_chance(&player, monster.level)) {
+            encounter.round += 1;
+            encounter.state = EncounterState::Fled;
+            character.effects.retain(|e| e.unit != DurationUnit::Turns);
+            if let Some(instance) = self.monster_instances.iter_mut().find(|m| m.instance_id == encounter.instance_id) {
+                instance.state = AiState::Returning;
+            }
+            let message = format!("You escaped from {}.", monster.name);
+            encounter.log.push(message.clone());
+            return Ok(message);
+        }
+
+        let message = format!("You failed to escape from {}.", monster.name);
+        self.resolve_round(0, vec![message])
+    }
+
+    pub fn get_encounter(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.encounter).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    fn start_encounter(&mut self, instance_id: Option<u32>) -> Result<(), JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.dead {
+            return Err(JsValue::from_str("You are dead"));
+        }
+        if character.health == 0 || character.defeated_until.is_some() {
+            return Err(JsValue::from_str("Too wounded to fight"));
+        }
+
+        let nearby = self.monster_instances.iter().filter(|m| m.location == character.location);
+        let instance = match instance_id {
+            Some(id) => nearby.clone().find(|m| m.instance_id == id).ok_or_else(|| JsValue::from_str("Monster not found"))?,
+            None => nearby.clone().min_by(|a, b| a.distance_to(character.x, character.y).total_cmp(&b.distance_to(character.x, character.y))).ok_or_else(|| JsValue::from_str("No monsters nearby"))?,
+        };
+        if instance.distance_to(character.x, character.y) > spawns::ENGAGE_RANGE {
+            return Err(JsValue::from_str(&format!("{} is too far away", instance.name)));
+        }
+        let monster = self.monsters.iter().find(|m| m.id == instance.monster_id).ok_or_else(|| JsValue::from_str("Monster not found"))?;
+        self.encounter = Some(Encounter::new(monster, instance));
+        Ok(())
+    }
+
+    pub fn fight_player(&mut self, target_wallet: String) -> Result<String, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if !zones::zone_of(&self.maps, &character.location).pvp {
+            return Err(JsValue::from_str("Cannot fight in a non-PvP zone"));
+        }
+        if character.dead {
+            return Err(JsValue::from_str("You are dead"));
+        }
+        if character.defeated_until.is_some() {
+            return Err(JsValue::from_str("You have been defeated and cannot fight"));
+        }
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Err(JsValue::from_str("Already in combat"));
+        }
+        if self.wallet.as_ref() == Some(&target_wallet) {
+            return Err(JsValue::from_str("You cannot attack yourself"));
+        }
+
+        let target = self.other_players.iter().find(|p| p.wallet == target_wallet).ok_or_else(|| JsValue::from_str("Player not found"))?;
+        let distance = ((target.x - character.x).powi(2) + (target.y - character.y).powi(2)).sqrt();
+        if target.location != character.location || distance > self.pvp_rules.range {
+            return Err(JsValue::from_str("Target is out of range"));
+        }
+        if target.defeated {
+            return Err(JsValue::from_str("Target is already defeated"));
+        }
+        if target.level.abs_diff(character.level) > self.pvp_rules.max_level_difference {
+            return Err(JsValue::from_str("Level difference too large"));
+        }
+        if let Some(&until) = self.pvp_cooldowns.get(&target_wallet).filter(|&&until| self.ticks < until) {
+            return Err(JsValue::from_str(&format!("You must wait {} more ticks before attacking {} again", until - self.ticks, target.name)));
+        }
+        let target_profile = target.combat.ok_or_else(|| JsValue::from_str("Target has no combat data"))?;
+        let unprovoked = !target.outlaw && !target.hostile;
+        let target_outlaw = target.outlaw;
+
+        let sheet = StatSheet::compute(character, &self.items);
+        let attacker_profile = CombatProfile::new(&sheet, character.health);
+        let (attacker_health, target_health, rounds, log) = pvp::simulate(&mut self.rng, (&character.name, attacker_profile), (&target.name, target_profile), self.pvp_rules.max_rounds);
+        character.health = attacker_health;
+        self.pvp_cooldowns.insert(target_wallet.clone(), self.ticks + self.pvp_rules.cooldown_ticks);
+
+        let wallet = self.wallet.clone().unwrap_or_default();
+        let attacker_won = if target_health == 0 {
+            Some(true)
+        } else if attacker_health == 0 {
+            Some(false)
+        } else {
+            None
+        };
+        let winner = attacker_won.map(|won| if won { wallet.clone() } else { target_wallet.clone() });
+        let (target_name, target_level) = (target.name.clone(), target.level);
+        self.last_pvp_result = Some(PvpResult {
+            attacker_wallet: wallet.clone(),
+            attacker_name: character.name.clone(),
+            target_wallet: target_wallet.clone(),
+            target_name: target_name.clone(),
+            winner,
+            rounds,
+            attacker_health,
+            target_health,
+            unprovoked,
+            log,
+        });
+
+        let mut messages = vec![format!("Fought {} (Level {}) for {} rounds.", target_name, target_level, rounds)];
+        if unprovoked {
+            character.hostile_until = Some(self.ticks + self.karma_rules.hostile_ticks);
+            messages.push(format!("You attacked {} unprovoked and lost {} karma.", target_name, self.karma_rules.attack_penalty));
+            messages.extend(karma::adjust_karma(character, -self.karma_rules.attack_penalty, &self.karma_rules));
+        }
+        if attacker_won == Some(true) {
+            if unprovoked {
+                messages.push(format!("Killing an innocent player cost you {} karma.", self.karma_rules.kill_penalty));
+                messages.extend(karma::adjust_karma(character, -self.karma_rules.kill_penalty, &self.karma_rules));
+            } else if target_outlaw {
+                messages.push(format!("Bringing down an outlaw earned you {} karma.", self.karma_rules.outlaw_kill_reward));
+                messages.extend(karma::adjust_karma(character, self.karma_rules.outlaw_kill_reward, &self.karma_rules));
+            }
+            messages.extend(self.claim_pvp_victory(target_level)?);
+        } else if attacker_won == Some(false) {
+            let loss = self.apply_pvp_defeat()?;
+            messages.push(format!("You were defeated by {} and {}.", target_name, self.describe_pvp_loss(&loss)));
+        } else {
+            messages.push("Neither of you fell.".to_string());
+        }
+        Ok(messages.join(" "))
+    }
+
+    pub fn receive_pvp_result(&mut self, result: JsValue) -> Result<String, JsValue> {
+        let result: PvpResult = result.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let wallet = self.wallet.clone().ok_or_else(|| JsValue::from_str("No wallet connected"))?;
+        if result.target_wallet != wallet {
+            return Err(JsValue::from_str("PvP result is for another player"));
+        }
+        if result.attacker_wallet == wallet {
+            return Err(JsValue::from_str("You cannot attack yourself"));
+        }
+
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        character.health = character.health.min(result.target_health);
+        let attacker = self.other_players.iter().find(|p| p.wallet == result.attacker_wallet);
+        let attacker_level = attacker.map_or(character.level, |p| p.level);
+        let attacker_outlaw = attacker.is_some_and(|p| p.outlaw);
+
+        let mut messages = vec![format!("{} attacked you for {} rounds.", result.attacker_name, result.rounds)];
+        if result.winner.as_ref() == Some(&result.attacker_wallet) {
+            let loss = self.apply_pvp_defeat()?;
+            messages.push(format!("You were defeated and {}.", self.describe_pvp_loss(&loss)));
+        } else if result.winner.as_ref() == Some(&wallet) {
+            if attacker_outlaw {
+                messages.push(format!("Bringing down an outlaw earned you {} karma.", self.karma_rules.outlaw_kill_reward));
+                messages.extend(karma::adjust_karma(character, self.karma_rules.outlaw_kill_reward, &self.karma_rules));
+            }
+            messages.extend(self.claim_pvp_victory(attacker_level)?);
+        } else {
+            messages.push("You survived the attack.".to_string());
+        }
+        Ok(messages.join(" "))
+    }
+
+    pub fn get_last_pvp_result(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.last_pvp_result).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn get_public_profile(&self) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let sheet = StatSheet::compute(character, &self.items);
+        let player = Player {
+            wallet: self.wallet.clone().unwrap_or_default(),
+            name: character.name.clone(),
+            level: character.level,
+            x: character.x,
+            y: character.y,
+            location: character.location.clone(),
+            equipment: character.equipment.clone(),
+            combat: Some(CombatProfile::new(&sheet, character.health)),
+            defeated: character.defeated_until.is_some(),
+            karma: character.karma,
+            outlaw: self.karma_rules.is_outlaw(character.karma),
+            hostile: character.hostile_until.is_some(),
+            arena: Some(character.arena.clone()),
+        };
+        JsValue::from_serde(&player).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn challenge_player(&mut self, target_wallet: String, ranked: bool) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.dead || character.defeated_until.is_some() {
+            return Err(JsValue::from_str("You are in no condition to duel"));
+        }
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Err(JsValue::from_str("Already in combat"));
+        }
+        if self.wallet.as_ref() == Some(&target_wallet) {
+            return Err(JsValue::from_str("You cannot duel yourself"));
+        }
+        let target = self.other_players.iter().find(|p| p.wallet == target_wallet).ok_or_else(|| JsValue::from_str("Player not found"))?;
+        let distance = ((target.x - character.x).powi(2) + (target.y - character.y).powi(2)).sqrt();
+        if target.location != character.location || distance > self.duel_rules.range {
+            return Err(JsValue::from_str("Target is out of range"));
+        }
+        if target.defeated {
+            return Err(JsValue::from_str("Target is in no condition to duel"));
+        }
+        if self.outgoing_duels.iter().any(|r| r.target_wallet == target_wallet) {
+            return Err(JsValue::from_str(&format!("You have already challenged {}", target.name)));
+        }
+
+        let request = DuelRequest {
+            challenger_wallet: self.wallet.clone().unwrap_or_default(),
+            challenger_name: character.name.clone(),
+            target_wallet,
+            ranked,
+            rating: character.arena.rating,
+            received_tick: self.ticks,
+        };
+        self.outgoing_duels.push(request.clone());
+        JsValue::from_serde(&request).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn receive_duel_request(&mut self, request: JsValue) -> Result<String, JsValue> {
+        let mut request: DuelRequest = request.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        if Some(&request.target_wallet) != self.wallet.as_ref() {
+            return Err(JsValue::from_str("Duel request is for another player"));
+        }
+        if request.challenger_wallet == request.target_wallet {
+            return Err(JsValue::from_str("You cannot duel yourself"));
+        }
+
+        request.received_tick = self.ticks;
+        self.incoming_duels.retain(|r| r.challenger_wallet != request.challenger_wallet);
+        let message = format!("{} challenges you to {}. Accept before it expires in {} ticks.", request.challenger_name, if request.ranked { "a ranked arena duel" } else { "a duel" }, self.duel_rules.request_ticks);
+        self.incoming_duels.push(request);
+        Ok(message)
+    }
+
+    pub fn get_duel_requests(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.incoming_duels).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn decline_duel(&mut self, challenger_wallet: String) -> Result<String, JsValue> {
+        let idx = self.incoming_duels.iter().position(|r| r.challenger_wallet == challenger_wallet).ok_or_else(|| JsValue::from_str("No pending duel from that player"))?;
+        let request = self.incoming_duels.remove(idx);
+        Ok(format!("Declined the duel from {}.", request.challenger_name))
+    }
+
+    pub fn accept_duel(&mut self, challenger_wallet: String) -> Result<String, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.dead || character.defeated_until.is_some() {
+            return Err(JsValue::from_str("You are in no condition to duel"));
+        }
+        if self.encounter.as_ref().is_some_and(|e| !e.is_over()) {
+            return Err(JsValue::from_str("Already in combat"));
+        }
+        if self.wallet.as_ref() == Some(&challenger_wallet) {
+            return Err(JsValue::from_str("You cannot duel yourself"));
+        }
+        let idx = self.incoming_duels.iter().position(|r| r.challenger_wallet == challenger_wallet).ok_or_else(|| JsValue::from_str("No pending duel from that player"))?;
+        let challenger = self.other_players.iter().find(|p| p.wallet == challenger_wallet).ok_or_else(|| JsValue::from_str("Challenger is no longer online"))?;
+        let mut challenger_profile = challenger.combat.ok_or_else(|| JsValue::from_str("Challenger has no combat data"))?;
+        let request = self.incoming_duels.remove(idx);
+
+        let sheet = StatSheet::compute(character, &self.items);
+        let own_profile = CombatProfile::new(&sheet, sheet.max_health);
+        challenger_profile.health = challenger_profile.max_health;
+        let (challenger_health, own_health, rounds, log) = pvp::simulate(&mut self.rng, (&request.challenger_name, challenger_profile), (&character.name, own_profile), self.duel_rules.max_rounds);
+
+        let wallet = self.wallet.clone().unwrap_or_default();
+        let winner = if challenger_health == 0 {
+            Some(wallet.clone())
+        } else if own_health == 0 {
+            Some(challenger_wallet.clone())
+        } else {
+            None
+        };
+        let result = DuelResult {
+            challenger_wallet,
+            challenger_name: request.challenger_name.clone(),
+            target_wallet: wallet.clone(),
+            target_name: character.name.clone(),
+            ranked: request.ranked,
+            winner: winner.clone(),
+            rounds,
+            challenger_rating: request.rating,
+            target_rating: character.arena.rating,
+            log,
+        };
+
+        let mut messages = vec![format!("Dueled {} for {} rounds.", request.challenger_name, rounds)];
+        messages.push(describe_duel_outcome(winner.as_ref(), &wallet));
+        if request.ranked {
+            let delta = duel::record_match(&mut character.arena, request.rating, duel_score(winner.as_ref(), &wallet), self.duel_rules.k_factor);
+            messages.push(duel::describe_rating_change(&character.arena, delta));
+        }
+        self.last_duel_result = Some(result);
+        Ok(messages.join(" "))
+    }
+
+    pub fn receive_duel_result(&mut self, result: JsValue) -> Result<String, JsValue> {
+        let result: DuelResult = result.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let wallet = self.wallet.clone().ok_or_else(|| JsValue::from_str("No wallet connected"))?;
+        if result.target_wallet == wallet {
+            return Err(JsValue::from_str("You cannot duel yourself"));
+        }
+        if result.challenger_wallet != wallet {
+            return Err(JsValue::from_str("Duel result is for another player"));
+        }
+        let idx = self.outgoing_duels.iter().position(|r| result.answers(r)).ok_or_else(|| JsValue::from_str("No matching duel challenge"))?;
+        let request = self.outgoing_duels.remove(idx);
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+
+        let mut messages = vec![format!("{} accepted your duel. Fought for {} rounds.", result.target_name, result.rounds)];
+        messages.push(describe_duel_outcome(result.winner.as_ref(), &wallet));
+        if request.ranked {
+            let delta = duel::record_match(&mut character.arena, result.target_rating, duel_score(result.winner.as_ref(), &wallet), self.duel_rules.k_factor);
+            messages.push(duel::describe_rating_change(&character.arena, delta));
+        }
+        self.last_duel_result = Some(result);
+        Ok(messages.join(" "))
+    }
+
+    pub fn get_last_duel_result(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.last_duel_result).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn get_leaderboard(&self, limit: usize) -> Result<JsValue, JsValue> {
+        let wallet = self.wallet.clone().unwrap_or_default();
+        let mut entries: Vec<LeaderboardEntry> = self.other_players.iter()
+            .filter(|p| p.wallet != wallet)
+            .filter_map(|p| p.arena.as_ref().map(|a| (p.wallet.clone(), p.name.clone(), p.level, a)))
+            .chain(self.character.as_ref().map(|c| (wallet.clone(), c.name.clone(), c.level, &c.arena)))
+            .map(|(wallet, name, level, arena)| LeaderboardEntry {
+                wallet,
+                name,
+                level,
+                rating: arena.rating,
+                wins: arena.wins,
+                losses: arena.losses,
+                draws: arena.draws,
+            })
+            .collect();
+        entries.sort_by(|a, b| b.rating.cmp(&a.rating).then(b.wins.cmp(&a.wins)).then(a.name.cmp(&b.name)));
+        entries.truncate(limit);
+        JsValue::from_serde(&entries).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    fn claim_pvp_victory(&mut self, opponent_level: u32) -> Result<Vec<String>, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let level_up = progression::grant_experience(character, (opponent_level * 5) as u64, &self.progression);
+        let bounty = (opponent_level * 10) as u64;
+        character.gold += bounty;
+
+        let mut messages = vec![format!("Victory! Gained {} EXP and {} gold.", level_up.experience_gained, bounty)];
+        messages.extend(progression::describe_level_up(&level_up));
+        Ok(messages)
+    }
+
+    fn apply_pvp_defeat(&mut self) -> Result<PvpLoss, JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let penalty_percent = self.karma_rules.penalty_percent(character.karma);
+        let gold = (character.gold * (self.pvp_rules.gold_loss_percent * penalty_percent) as u64 / 10000).min(character.gold);
+        character.gold -= gold;
+        character.health = 0;
+        character.defeated_until = Some(self.ticks + self.pvp_rules.defeat_ticks);
+        character.effects.retain(|e| e.unit != DurationUnit::Turns);
+
+        let mut item_id = None;
+        if !character.inventory.is_empty() && self.rng.gen_bool((self.pvp_rules.item_drop_chance * penalty_percent as f64 / 100.0).min(1.0)) {
+            let idx = self.rng.gen_range(0..character.inventory.len());
+            let entry = &mut character.inventory[idx];
+            item_id = Some(entry.item_id);
+            entry.quantity -= 1;
+            character.inventory.retain(|i| i.quantity > 0);
+        }
+        Ok(PvpLoss { gold, item_id })
+    }
+
+    fn describe_pvp_loss(&self, loss: &PvpLoss) -> String {
+        match loss.item_id.and_then(|id| self.item_name(id)) {
+            Some(name) => format!("lost {} gold and your {}", loss.gold, name),
+            None => format!("lost {} gold", loss.gold),
+        }
+    }
+
+    pub fn unlock_skill_node(&mut self, node_id: u32) -> Result<(), JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        if character.skill_points == 0 {
+            return Err(JsValue::from_str("No skill points available"));
+        }
+
+        let has_unlocked_neighbor = character.skill_tree.iter().any(|n| n.unlocked && n.connections.contains(&node_id));
+        let node = character.skill_tree.iter_mut().find(|n| n.id == node_id).ok_or_else(|| JsValue::from_str("Node not found"))?;
+        if node.unlocked {
+            return Err(JsValue::from_str("Node already unlocked"));
+        }
+
+        if !has_unlocked_neighbor && node_id != 0 {
+            return Err(JsValue::from_str("Must unlock a connected node first"));
+        }
+
+        let status = match &node.effect {
+            Some(status_id) => Some(self.status_effects.iter().find(|e| &e.id == status_id).ok_or_else(|| JsValue::from_str("Status effect not found"))?),
+            None => None,
+        };
+
+        node.unlocked = true;
+        character.skill_points -= 1;
+
+        if let Some(status) = status {
+            effects::apply_effect(&mut character.effects, status);
+        }
+
+        Ok(())
+    }
+
+    pub fn equip_item(&mut self, item_id: u32) -> Result<(), JsValue> {
+        let item = self.items.iter().find(|i| i.id == item_id).ok_or_else(|| JsValue::from_str("Item not found"))?.clone();
+        if !items::is_equippable(&item) {
+            return Err(JsValue::from_str("Item cannot be equipped"));
+        }
+
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let instance = match items::take_instance(&mut character.inventory, item_id) {
+            Some(instance) => instance,
+            None => {
+                self.remove_from_inventory(item_id, 1)?;
+                let character = self.character.as_mut().unwrap();
+                character.next_instance_id += 1;
+                ItemInstance::plain(character.next_instance_id - 1, &item)
+            }
+        };
+
+        let character = self.character.as_mut().unwrap();
+        let slot = character.equipment.slot_mut(&item.slot).ok_or_else(|| JsValue::from_str("Invalid equipment slot"))?;
+        if let Some(displaced) = slot.replace(instance) {
+            character.inventory.push(InventoryItem { item_id: displaced.item_id, quantity: 1, instance: Some(displaced) });
+        }
+
+        self.clamp_resources();
+        Ok(())
+    }
+
+    pub fn unequip_item(&mut self, slot: String) -> Result<(), JsValue> {
+        let character = self.character.as_mut().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let slot = character.equipment.slot_mut(&slot).ok_or_else(|| JsValue::from_str("Invalid equipment slot"))?;
+        let instance = slot.take().ok_or_else(|| JsValue::from_str("Slot is empty"))?;
+
+        character.inventory.push(InventoryItem { item_id: instance.item_id, quantity: 1, instance: Some(instance) });
+        self.clamp_resources();
+        Ok(())
+    }
+
+    pub fn item_name(&self, item_id: u32) -> Option<String> {
+        self.items.iter().find(|i| i.id == item_id).map(|i| i.name.clone())
+    }
+
+    fn clamp_resources(&mut self) {
+        if let Some(character) = self.character.as_mut() {
+            let sheet = StatSheet::compute(character, &self.items);
+            character.health = character.health.min(sheet.max_health);
+            character.mana = character.mana.min(sheet.max_mana);
+        }
+    }
+
+    pub fn get_stat_sheet(&self) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        JsValue::from_serde(&StatSheet::compute(character, &self.items)).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn get_character(&self) -> Result<JsValue, JsValue> {
+        match &self.character {
+            Some(character) => JsValue::from_serde(character).map_err(|e| JsValue::from_str(&e.to_string())),
+            None => Err(JsValue::from_str("No character created")),
+        }
+    }
+
+    pub fn get_progress(&self) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let progress = serde_json::json!({
+            "level": character.level,
+            "experience": character.experience,
+            "next_level_experience": self.progression.experience_for_level(character.level),
+            "level_cap": self.progression.level_cap,
+        });
+        JsValue::from_serde(&progress).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn get_inventory(&self) -> Result<JsValue, JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        JsValue::from_serde(&character.inventory).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn render_game(&self, canvas_id: String, show_skill_tree: bool) -> Result<(), JsValue> {
+        let character = self.character.as_ref().ok_or_else(|| JsValue::from_str("No character created"))?;
+        let document = web_sys::window().unwrap().document().unwrap();
+        let canvas = document.get_element_by_id(&canvas_id).ok_or_else(|| JsValue::from_str("Canvas not found"))?;
+        let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>().map_err(|_| JsValue::from_str("Not a canvas"))?;
+        let gl = canvas.get_context("webgl")?.ok_or_else(|| JsValue::from_str("WebGL not supported"))?.dyn_into::<GL>()?;
+
+        if show_skill_tree {
+            return self.render_skill_tree(canvas_id);
+        }
+
+        let vert_shader = gl.create_shader(GL::VERTEX_SHADER).ok_or_else(|| JsValue::from_str("Unable to create shader"))?;
+        gl.shader_source(&vert_shader, r#"
+            attribute vec2 a_position;
+            uniform vec2 u_resolution;
+            uniform vec2 u_camera;
+            void main() {
+                vec2 world = a_position - u_camera;
+                vec2 iso_pos = vec2(world.x - world.y, (world.x + world.y) * 0.5);
+                vec2 normalized = iso_pos / u_resolution * 2.0 - 1.0;
+                gl_Position = vec4(normalized, 0.0, 1.0);
+                gl_PointSize = 20.0;
+            }
+        "#);
+        gl.compile_shader(&vert_shader);
+
+        let frag_shader = gl.create_shader(GL::FRAGMENT_SHADER).ok_or_else(|| JsValue::from_str("Unable to create shader"))?;
+        gl.shader_source(&frag_shader, r#"
+            precision mediump float;
+            uniform vec4 u_color;
+            void main() {
+                gl_FragColor = u_color;
+            }
+        "#);
+        gl.compile_shader(&frag_shader);
+
+        let program = gl.create_program().ok_or_else(|| JsValue::from_str("Unable to create program"))?;
+        gl.attach_shader(&program, &vert_shader);
+        gl.attach_shader(&program, &frag_shader);
+        gl.link_program(&program);
+        gl.use_program(Some(&program));
 
         let resolution_location = gl.get_uniform_location(&program, "u_resolution").ok_or_else(|| JsValue::from_str("Uniform not found"))?;
         gl.uniform2f(Some(&resolution_location), canvas.width() as f32, canvas.height() as f32);
 
         let map = self.maps.get(&character.location).ok_or_else(|| JsValue::from_str("Map not loaded"))?;
+        let tile_size = 32.0;
+        let camera = chunks::camera_origin(map, character.x, character.y);
+        let camera_location = gl.get_uniform_location(&program, "u_camera").ok_or_else(|| JsValue::from_str("Uniform not found"))?;
+        gl.uniform2f(Some(&camera_location), camera.0 * tile_size, camera.1 * tile_size);
         let position_location = gl.get_attrib_location(&program, "a_position") as u32;
         let color_location = gl.get_uniform_location(&program, "u_color").ok_or_else(|| JsValue::from_str("Uniform not found"))?;
         let position_buffer = gl.create_buffer().ok_or_else(|| JsValue::from_str("Buffer creation failed"))?;
@@ -493,33 +2002,58 @@ impl Game {
         gl.enable_vertex_attrib_array(position_location);
         gl.vertex_attrib_pointer_with_i32(position_location, 2, GL::FLOAT, false, 0, 0);
 
-        let tile_size = 32.0;
-        for y in 0..map.height as usize {
-            for x in 0..map.width as usize {
-                let tile = &map.tiles[y][x];
-                let color = match tile.as_str() {
-                    "grass" => [0.0, 1.0, 0.0, 1.0],
-                    "stone" => [0.5, 0.5, 0.5, 1.0],
-                    "water" => [0.0, 0.0, 1.0, 1.0],
-                    _ => [1.0, 1.0, 1.0, 1.0],
+        let (min_x, min_y, max_x, max_y) = chunks::view_bounds(map, character.x, character.y);
+        let draw_tile = |x: u32, y: u32, color: &[f32]| {
+            gl.uniform4fv_with_f32_array(Some(&color_location), color);
+            let cx = (x as f32 + 0.5) * tile_size;
+            let cy = (y as f32 + 0.5) * tile_size;
+            let positions = [
+                cx - tile_size / 2.0, cy,
+                cx, cy - tile_size / 4.0,
+                cx + tile_size / 2.0, cy,
+                cx, cy + tile_size / 4.0,
+            ];
+            gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_sys::Float32Array::from(positions.as_ref()).into(), GL::STATIC_DRAW);
+            gl.draw_arrays(GL::TRIANGLE_FAN, 0, 4);
+        };
+        let draw_layer = |layer: &Layer, hidden: &std::collections::HashSet<(u32, u32)>| {
+            for placement in &layer.placements {
+                let color = tiles::find(&self.tiles, &placement.tile).map_or([1.0, 1.0, 1.0, 1.0], |t| t.color);
+                for (x, y) in placement.cells(&self.tiles) {
+                    let visible = (min_x..max_x).contains(&(x as usize)) && (min_y..max_y).contains(&(y as usize));
+                    if visible && !hidden.contains(&(x, y)) {
+                        draw_tile(x, y, &color);
+                    }
+                }
+            }
+        };
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let Some(tile) = map.tile(x, y) else {
+                    continue;
                 };
-                gl.uniform4fv_with_f32_array(Some(&color_location), &color);
-                let cx = (x as f32 + 0.5) * tile_size;
-                let cy = (y as f32 + 0.5) * tile_size;
-                let positions = [
-                    cx - tile_size / 2.0, cy,
-                    cx, cy - tile_size / 4.0,
-                    cx + tile_size / 2.0, cy,
-                    cx, cy + tile_size / 4.0,
-                ];
-                gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_sys::Float32Array::from(positions.as_ref()).into(), GL::STATIC_DRAW);
-                gl.draw_arrays(GL::TRIANGLE_FAN, 0, 4);
+                let color = if map.portals.iter().any(|p| p.x as usize == x && p.y as usize == y) {
+                    [0.8, 0.3, 1.0, 1.0]
+                } else {
+                    tiles::find(&self.tiles, tile).map_or([1.0, 1.0, 1.0, 1.0], |t| t.color)
+                };
+                draw_tile(x as u32, y as u32, &color);
             }
         }
+        for layer in map.layers.iter().filter(|l| l.kind != LayerKind::Roof) {
+            draw_layer(layer, &std::collections::HashSet::new());
+        }
 
         for player in &self.other_players {
             if player.location == character.location {
-                gl.uniform4fv_with_f32_array(Some(&color_location), &[1.0, 0.0, 0.0, 1.0]);
+                let color = if player.outlaw {
+                    [0.6, 0.0, 0.6, 1.0]
+                } else if player.hostile {
+                    [1.0, 0.5, 0.0, 1.0]
+                } else {
+                    [1.0, 0.0, 0.0, 1.0]
+                };
+                gl.uniform4fv_with_f32_array(Some(&color_location), &color);
                 let cx = player.x * tile_size;
                 let cy = player.y * tile_size;
                 let positions = [
@@ -533,6 +2067,20 @@ impl Game {
             }
         }
 
+        gl.uniform4fv_with_f32_array(Some(&color_location), &[0.4, 0.2, 0.0, 1.0]);
+        for monster in self.monster_instances.iter().filter(|m| m.location == character.location) {
+            let cx = monster.x * tile_size;
+            let cy = monster.y * tile_size;
+            let positions = [
+                cx - tile_size / 4.0, cy,
+                cx, cy - tile_size / 8.0,
+                cx + tile_size / 4.0, cy,
+                cx, cy + tile_size / 8.0,
+            ];
+            gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_sys::Float32Array::from(positions.as_ref()).into(), GL::STATIC_DRAW);
+            gl.draw_arrays(GL::TRIANGLE_FAN, 0, 4);
+        }
+
         gl.uniform4fv_with_f32_array(Some(&color_location), &[0.0, 1.0, 0.0, 1.0]);
         let cx = character.x * tile_size;
         let cy = character.y * tile_size;
@@ -545,6 +2093,11 @@ impl Game {
         gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &js_sys::Float32Array::from(positions.as_ref()).into(), GL::STATIC_DRAW);
         gl.draw_arrays(GL::TRIANGLE_FAN, 0, 4);
 
+        let hidden = layers::hidden_roof_cells(map, &self.tiles, character.x, character.y);
+        for layer in map.layers.iter().filter(|l| l.kind == LayerKind::Roof) {
+            draw_layer(layer, &hidden);
+        }
+
         Ok(())
     }
 
@@ -618,6 +2171,113 @@ impl Game {
     }
 }
 
+impl Race {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Race::Human => "Human",
+            Race::Elf => "Elf",
+            Race::Dwarf => "Dwarf",
+            Race::Orc => "Orc",
+        }
+    }
+}
+
+impl Profession {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Profession::Warrior => "Warrior",
+            Profession::Mage => "Mage",
+            Profession::Archer => "Archer",
+            Profession::Blacksmith => "Blacksmith",
+        }
+    }
+}
+
+impl Character {
+    pub fn base_stat(&self, stat: &str) -> u32 {
+        match stat {
+            "strength" => self.strength,
+            "endurance" => self.endurance,
+            "wisdom" => self.wisdom,
+            "mystic" => self.mystic,
+            "agility" => self.agility,
+            "accuracy" => self.accuracy,
+            "intellect" => self.intellect,
+            "luck" => self.luck,
+            "sword" => self.sword,
+            "spear" => self.spear,
+            "axe" => self.axe,
+            "dagger" => self.dagger,
+            "bow" => self.bow,
+            "shield_skill" => self.shield_skill,
+            "magic" => self.magic,
+            "rune_magic" => self.rune_magic,
+            "magic_resistance" => self.magic_resistance,
+            "healing" => self.healing,
+            "mining" => self.mining,
+            "fishing" => self.fishing,
+            "alchemy" => self.alchemy,
+            _ => 0,
+        }
+    }
+
+    pub fn base_stat_mut(&mut self, stat: &str) -> Option<&mut u32> {
+        match stat {
+            "strength" => Some(&mut self.strength),
+            "endurance" => Some(&mut self.endurance),
+            "wisdom" => Some(&mut self.wisdom),
+            "mystic" => Some(&mut self.mystic),
+            "agility" => Some(&mut self.agility),
+            "accuracy" => Some(&mut self.accuracy),
+            "intellect" => Some(&mut self.intellect),
+            "luck" => Some(&mut self.luck),
+            "sword" => Some(&mut self.sword),
+            "spear" => Some(&mut self.spear),
+            "axe" => Some(&mut self.axe),
+            "dagger" => Some(&mut self.dagger),
+            "bow" => Some(&mut self.bow),
+            "shield_skill" => Some(&mut self.shield_skill),
+            "magic" => Some(&mut self.magic),
+            "rune_magic" => Some(&mut self.rune_magic),
+            "magic_resistance" => Some(&mut self.magic_resistance),
+            "healing" => Some(&mut self.healing),
+            "mining" => Some(&mut self.mining),
+            "fishing" => Some(&mut self.fishing),
+            "alchemy" => Some(&mut self.alchemy),
+            _ => None,
+        }
+    }
+}
+
+impl Equipment {
+    pub fn slots(&self) -> [&Option<ItemInstance>; 9] {
+        [&self.armor, &self.helmet, &self.amulet, &self.gloves, &self.ring, &self.weapon, &self.shield, &self.legs, &self.boots]
+    }
+
+    pub fn slots_mut(&mut self) -> [&mut Option<ItemInstance>; 9] {
+        [&mut self.armor, &mut self.helmet, &mut self.amulet, &mut self.gloves, &mut self.ring, &mut self.weapon, &mut self.shield, &mut self.legs, &mut self.boots]
+    }
+
+    pub fn armor_pieces_mut(&mut self) -> Vec<&mut ItemInstance> {
+        [&mut self.armor, &mut self.helmet, &mut self.gloves, &mut self.legs, &mut self.boots, &mut self.shield].into_iter().filter_map(|s| s.as_mut()).collect()
+    }
+
+    pub fn slot_mut(&mut self, slot: &str) -> Option<&mut Option<ItemInstance>> {
+        match slot {
+            "armor" => Some(&mut self.armor),
+            "helmet" => Some(&mut self.helmet),
+            "amulet" => Some(&mut self.amulet),
+            "gloves" => Some(&mut self.gloves),
+            "ring" => Some(&mut self.ring),
+            "weapon" => Some(&mut self.weapon),
+            "shield" => Some(&mut self.shield),
+            "legs" => Some(&mut self.legs),
+            "boots" => Some(&mut self.boots),
+            _ => None,
+        }
+    }
+}
+
 impl Default for Equipment {
     fn default() -> Self {
         Equipment {
@@ -634,10 +2294,230 @@ impl Default for Equipment {
     }
 }
 
-fn is_pvp_zone(location: &str) -> bool {
-    match location {
-        "Town" | "Temple" | "Building" => false,
-        "Wilderness" | "HuntingGround" | "BossArea" | "QuestArea" | "Castle" | "Island" => true,
-        _ => true,
+#[wasm_bindgen]
+pub fn validate_map(location: String, map_data: JsValue, tiles_data: JsValue) -> Result<JsValue, JsValue> {
+    let mut map: Map = map_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let tile_types: Vec<TileType> = tiles_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+    map.layer_collisions = layers::collision_cells(&map, &tile_types);
+    let reports: Vec<validation::ProblemReport> = validation::validate(&location, &map, &tile_types).into_iter().map(MapProblem::report).collect();
+    JsValue::from_serde(&reports).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+#[wasm_bindgen]
+pub fn encode_map(map_data: JsValue) -> Result<Vec<u8>, JsValue> {
+    let map: Map = map_data.into_serde().map_err(|e| JsValue::from_str(&e.to_string()))?;
+    compact::encode(&map).map_err(|e| JsValue::from_str(&e))
+}
+
+#[wasm_bindgen]
+pub fn decode_map(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let map = compact::decode(bytes).map_err(|e| JsValue::from_str(&e))?;
+    JsValue::from_serde(&map).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+impl Map {
+    fn chunk_cell(&self, x: usize, y: usize) -> Option<(&Chunk, usize, usize)> {
+        let size = self.chunk_size? as usize;
+        let chunk = self.chunks.get(&((x / size) as u32, (y / size) as u32))?;
+        Some((chunk, x % size, y % size))
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Option<&String> {
+        if self.chunk_size.is_some() {
+            let (chunk, cx, cy) = self.chunk_cell(x, y)?;
+            return chunk.tiles.get(cy)?.get(cx);
+        }
+        self.tiles.get(y)?.get(x)
+    }
+
+    pub fn blocked(&self, x: usize, y: usize) -> bool {
+        let cell = if self.chunk_size.is_some() {
+            self.chunk_cell(x, y).and_then(|(chunk, cx, cy)| chunk.collisions.get(cy)?.get(cx))
+        } else {
+            self.collisions.get(y).and_then(|row| row.get(x))
+        };
+        cell.copied().unwrap_or(true) || self.layer_collisions.contains(&(x as u32, y as u32))
+    }
+
+    pub fn is_loaded(&self, x: usize, y: usize) -> bool {
+        self.chunk_size.is_none() || self.chunk_cell(x, y).is_some()
+    }
+}
+
+fn nearest_npc<'a>(npcs: &'a [Npc], role: &str, location: &str, (x, y): (f32, f32), range: f32) -> Option<&'a Npc> {
+    npcs.iter()
+        .filter(|n| n.role == role && n.location == location)
+        .map(|n| (n, ((n.x - x).powi(2) + (n.y - y).powi(2)).sqrt()))
+        .filter(|&(_, distance)| distance <= range)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(n, _)| n)
+}
+
+fn weapon_skill(equipment: &Equipment, items: &[Item]) -> Option<String> {
+    let weapon = equipment.weapon.as_ref().filter(|w| !w.is_broken())?;
+    items.iter().find(|i| i.id == weapon.item_id)?.skill.clone()
+}
+
+fn ability_power(sheet: &StatSheet, ability: &Ability) -> u32 {
+    match (&ability.profession, &ability.effect) {
+        (Profession::Mage, AbilityEffect::Damage { .. }) => (sheet.stat("magic") + sheet.stat("intellect")) / 2,
+        (Profession::Mage, AbilityEffect::Heal { .. }) => (sheet.stat("healing") + sheet.stat("wisdom")) / 2,
+        (_, AbilityEffect::Damage { .. }) => sheet.attack / 2,
+        (_, AbilityEffect::Heal { .. }) => sheet.stat("healing") / 2,
+        (_, AbilityEffect::Status) => 0,
+    }
+}
+
+fn duel_score(winner: Option<&String>, wallet: &str) -> f64 {
+    match winner {
+        Some(w) if w == wallet => 1.0,
+        Some(_) => 0.0,
+        None => 0.5,
+    }
+}
+
+fn describe_duel_outcome(winner: Option<&String>, wallet: &str) -> String {
+    match winner {
+        Some(w) if w == wallet => "You won the duel!".to_string(),
+        Some(_) => "You lost the duel.".to_string(),
+        None => "The duel ended in a draw.".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME_DATA: &str = include_str!("../public/game_data.json");
+    const MAPS: [(&str, &str); 9] = [
+        ("Town", include_str!("../public/maps/Town.json")),
+        ("Temple", include_str!("../public/maps/Temple.json")),
+        ("Building", include_str!("../public/maps/Building.json")),
+        ("Wilderness", include_str!("../public/maps/Wilderness.json")),
+        ("HuntingGround", include_str!("../public/maps/HuntingGround.json")),
+        ("BossArea", include_str!("../public/maps/BossArea.json")),
+        ("QuestArea", include_str!("../public/maps/QuestArea.json")),
+        ("Castle", include_str!("../public/maps/Castle.json")),
+        ("Island", include_str!("../public/maps/Island.json")),
+    ];
+    const WILDERNESS_CHUNKS: [((u32, u32), &str); 4] = [
+        ((0, 0), include_str!("../public/maps/Wilderness/0_0.json")),
+        ((1, 0), include_str!("../public/maps/Wilderness/1_0.json")),
+        ((0, 1), include_str!("../public/maps/Wilderness/0_1.json")),
+        ((1, 1), include_str!("../public/maps/Wilderness/1_1.json")),
+    ];
+
+    fn game() -> Game {
+        let data: serde_json::Value = serde_json::from_str(GAME_DATA).unwrap();
+        let mut game = Game::new();
+        game.items = serde_json::from_value(data["items"].clone()).unwrap();
+        game.monsters = serde_json::from_value(data["monsters"].clone()).unwrap();
+        game.tiles = serde_json::from_value(data["tiles"].clone()).unwrap();
+        game.npcs = serde_json::from_value(data["npcs"].clone()).unwrap();
+        game.death_rules = serde_json::from_value(data["death"].clone()).unwrap();
+        game.karma_rules = serde_json::from_value(data["karma"].clone()).unwrap();
+        game.spawns = serde_json::from_value(data["spawns"].clone()).unwrap();
+        game.abilities = serde_json::from_value(data["abilities"].clone()).unwrap();
+        game.status_effects = serde_json::from_value(data["status_effects"].clone()).unwrap();
+        game.loot_tables = serde_json::from_value(data["loot_tables"].clone()).unwrap();
+        game.progression = serde_json::from_value(data["progression"].clone()).unwrap();
+        for (location, json) in MAPS {
+            game.insert_map(location, serde_json::from_str(json).unwrap()).unwrap();
+        }
+        game
+    }
+
+    fn hero(game: &mut Game) {
+        game.create_character("Hero".to_string(), "Human".to_string(), "Warrior".to_string(), "hero-wallet".to_string()).unwrap();
+        game.move_budget = 1.0;
+    }
+
+    fn engage(game: &mut Game) {
+        let (monster, instance) = game.monster_instances.iter()
+            .filter_map(|i| game.monsters.iter().find(|m| m.id == i.monster_id && m.boss.is_none()).map(|m| (m, i)))
+            .next()
+            .unwrap();
+        game.encounter = Some(Encounter::new(monster, instance));
+    }
+
+    #[test]
+    fn round_damages_monster_and_advances() {
+        let mut game = game();
+        hero(&mut game);
+        engage(&mut game);
+        let health = game.encounter.as_ref().unwrap().monster_health;
+        game.resolve_round(1, Vec::new()).unwrap();
+        let encounter = game.encounter.as_ref().unwrap();
+        assert_eq!(encounter.round, 1);
+        assert_eq!(encounter.monster_health, health - 1);
+        assert!(!encounter.log.is_empty());
+    }
+
+    #[test]
+    fn killing_blow_wins_and_queues_respawn() {
+        let mut game = game();
+        hero(&mut game);
+        engage(&mut game);
+        let (instance_id, health) = game.encounter.as_ref().map(|e| (e.instance_id, e.monster_health)).unwrap();
+        let message = game.resolve_round(health, Vec::new()).unwrap();
+        assert!(message.starts_with("Defeated"));
+        assert!(game.encounter.as_ref().unwrap().state == EncounterState::Won);
+        assert!(!game.monster_instances.iter().any(|m| m.instance_id == instance_id));
+        assert_eq!(game.pending_respawns.len(), 1);
+        assert!(game.character.as_ref().unwrap().experience > 0);
+    }
+
+    #[test]
+    fn moving_is_rejected_in_combat() {
+        let mut game = game();
+        hero(&mut game);
+        engage(&mut game);
+        assert_eq!(game.move_character(5.5, 5.0).unwrap_err(), "Cannot move while in combat");
+        let character = game.character.as_ref().unwrap();
+        assert_eq!((character.x, character.y), (5.0, 5.0));
+
+        game.encounter.as_mut().unwrap().state = EncounterState::Fled;
+        assert!(game.move_character(5.5, 5.0).is_ok());
+    }
+
+    #[test]
+    fn portals_are_blocked_in_combat() {
+        let mut game = game();
+        hero(&mut game);
+        engage(&mut game);
+        let character = game.character.as_mut().unwrap();
+        (character.x, character.y) = (9.5, 5.5);
+        assert_eq!(game.enter_portal().unwrap(), "You cannot take East Gate while in combat.");
+        assert_eq!(game.character.as_ref().unwrap().location, "Town");
+
+        game.encounter.as_mut().unwrap().state = EncounterState::Won;
+        game.insert_chunk("Wilderness", WILDERNESS_CHUNKS[0].0, serde_json::from_str(WILDERNESS_CHUNKS[0].1).unwrap()).unwrap();
+        assert_eq!(game.enter_portal().unwrap(), "You travel to East Gate.");
+        assert_eq!(game.character.as_ref().unwrap().location, "Wilderness");
+    }
+
+    #[test]
+    fn blacksmith_must_be_close_by() {
+        let game = game();
+        let smith = nearest_npc(&game.npcs, "blacksmith", "Town", (8.0, 4.5), items::REPAIR_RANGE).unwrap();
+        assert_eq!(smith.role, "blacksmith");
+        assert!(nearest_npc(&game.npcs, "blacksmith", "Town", (5.0, 5.0), items::REPAIR_RANGE).is_none());
+        assert!(nearest_npc(&game.npcs, "blacksmith", "Temple", (8.0, 3.0), items::REPAIR_RANGE).is_none());
+    }
+
+    #[test]
+    fn shipped_chunks_load() {
+        let mut game = game();
+        for (position, json) in WILDERNESS_CHUNKS {
+            game.insert_chunk("Wilderness", position, serde_json::from_str(json).unwrap()).unwrap();
+        }
+        assert_eq!(game.maps["Wilderness"].chunks.len(), WILDERNESS_CHUNKS.len());
+    }
+
+    #[test]
+    fn chunk_outside_map_is_rejected() {
+        let mut game = game();
+        let chunk = serde_json::from_str(WILDERNESS_CHUNKS[0].1).unwrap();
+        assert!(game.insert_chunk("Wilderness", (2, 0), chunk).is_err());
     }
 }
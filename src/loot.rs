@@ -0,0 +1,170 @@
+use serde::{Serialize, Deserialize};
+use rand::prelude::*;
+
+use crate::items::Rarity;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LootEntry {
+    pub item_id: u32,
+    #[serde(default)]
+    pub weight: u32,
+    #[serde(default = "default_quantity")]
+    pub quantity: [u32; 2],
+    #[serde(default)]
+    pub min_level: u32,
+    #[serde(default)]
+    pub max_level: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LootTable {
+    pub id: u32,
+    #[serde(default = "default_rolls")]
+    pub rolls: u32,
+    #[serde(default)]
+    pub empty_weight: u32,
+    #[serde(default)]
+    pub guaranteed: Vec<LootEntry>,
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LootDrop {
+    pub item_id: u32,
+    pub name: String,
+    pub quantity: u32,
+    pub instance_id: Option<u32>,
+    pub rarity: Option<Rarity>,
+}
+
+fn default_quantity() -> [u32; 2] {
+    [1, 1]
+}
+
+fn default_rolls() -> u32 {
+    1
+}
+
+impl LootEntry {
+    pub fn allows_level(&self, level: u32) -> bool {
+        level >= self.min_level && level <= self.max_level.unwrap_or(u32::MAX)
+    }
+
+    fn roll_quantity(&self, rng: &mut impl Rng) -> u32 {
+        let (min, max) = (self.quantity[0].min(self.quantity[1]), self.quantity[0].max(self.quantity[1]));
+        rng.gen_range(min..=max)
+    }
+}
+
+pub fn roll_loot(rng: &mut impl Rng, table: &LootTable, level: u32, luck: u32) -> Vec<(u32, u32)> {
+    let mut drops: Vec<(u32, u32)> = table.guaranteed.iter()
+        .filter(|e| e.allows_level(level))
+        .map(|e| (e.item_id, e.roll_quantity(rng)))
+        .collect();
+
+    let entries: Vec<&LootEntry> = table.entries.iter().filter(|e| e.allows_level(level) && e.weight > 0).collect();
+    if entries.is_empty() {
+        return drops;
+    }
+
+    let empty_weight = table.empty_weight * 100 / (100 + luck * 5);
+    let total: u32 = entries.iter().map(|e| e.weight).sum::<u32>() + empty_weight;
+    let extra_roll = rng.gen_bool((luck as f64 / 100.0).min(0.5));
+    for _ in 0..table.rolls + extra_roll as u32 {
+        let mut pick = rng.gen_range(0..total);
+        if pick < empty_weight {
+            continue;
+        }
+        pick -= empty_weight;
+        for entry in &entries {
+            if pick < entry.weight {
+                drops.push((entry.item_id, entry.roll_quantity(rng)));
+                break;
+            }
+            pick -= entry.weight;
+        }
+    }
+    drops
+}
+
+pub fn describe_drops(drops: &[LootDrop]) -> String {
+    if drops.is_empty() {
+        return "no loot".to_string();
+    }
+    drops.iter().map(|d| match (&d.rarity, d.quantity) {
+        (Some(rarity), _) => format!("{} {}", rarity.name(), d.name),
+        (None, 1) => d.name.clone(),
+        (None, quantity) => format!("{}x {}", quantity, d.name),
+    }).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(item_id: u32, weight: u32) -> LootEntry {
+        LootEntry { item_id, weight, quantity: [1, 1], min_level: 0, max_level: None }
+    }
+
+    fn table(entries: Vec<LootEntry>) -> LootTable {
+        LootTable { id: 1, rolls: 1, empty_weight: 0, guaranteed: Vec::new(), entries }
+    }
+
+    fn drop(name: &str, quantity: u32, rarity: Option<Rarity>) -> LootDrop {
+        LootDrop { item_id: 1, name: name.to_string(), quantity, instance_id: None, rarity }
+    }
+
+    #[test]
+    fn level_range_is_inclusive() {
+        let entry = LootEntry { min_level: 3, max_level: Some(5), ..entry(1, 1) };
+        assert!(!entry.allows_level(2));
+        assert!(entry.allows_level(3));
+        assert!(entry.allows_level(5));
+        assert!(!entry.allows_level(6));
+        assert!(LootEntry { max_level: None, ..entry }.allows_level(u32::MAX));
+    }
+
+    #[test]
+    fn guaranteed_entries_always_drop() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let table = LootTable { guaranteed: vec![LootEntry { quantity: [2, 2], ..entry(9, 0) }], ..table(Vec::new()) };
+        for _ in 0..20 {
+            assert_eq!(roll_loot(&mut rng, &table, 1, 0), vec![(9, 2)]);
+        }
+    }
+
+    #[test]
+    fn zero_weight_and_out_of_level_entries_never_drop() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let table = table(vec![entry(1, 0), LootEntry { min_level: 10, ..entry(2, 5) }, entry(3, 5)]);
+        for _ in 0..50 {
+            assert_eq!(roll_loot(&mut rng, &table, 1, 0), vec![(3, 1)]);
+        }
+    }
+
+    #[test]
+    fn empty_weight_can_roll_nothing() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let table = LootTable { empty_weight: 1000, ..table(vec![entry(1, 1)]) };
+        let empty = (0..100).filter(|_| roll_loot(&mut rng, &table, 1, 0).is_empty()).count();
+        assert!(empty > 90);
+    }
+
+    #[test]
+    fn quantities_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let table = table(vec![LootEntry { quantity: [5, 2], ..entry(1, 1) }]);
+        for _ in 0..50 {
+            for (_, quantity) in roll_loot(&mut rng, &table, 1, 0) {
+                assert!((2..=5).contains(&quantity));
+            }
+        }
+    }
+
+    #[test]
+    fn drops_are_described() {
+        assert_eq!(describe_drops(&[]), "no loot");
+        assert_eq!(describe_drops(&[drop("Bone", 1, None), drop("Coin", 3, None)]), "Bone, 3x Coin");
+        assert_eq!(describe_drops(&[drop("Iron Sword", 1, Some(Rarity::Rare))]), format!("{} Iron Sword", Rarity::Rare.name()));
+    }
+}
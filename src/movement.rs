@@ -0,0 +1,103 @@
+use crate::pathfinding::{self, Terrain};
+
+const BASE_SPEED: f32 = 1.0;
+const SPEED_PER_AGILITY: f32 = 0.02;
+const MAX_SPEED: f32 = 2.5;
+const BURST_TICKS: f32 = 2.0;
+const SWEEP_STEP: f32 = 0.25;
+const EDGE_EPSILON: f32 = 0.001;
+
+pub fn speed(agility: u32) -> f32 {
+    (BASE_SPEED + agility as f32 * SPEED_PER_AGILITY).min(MAX_SPEED)
+}
+
+pub fn max_budget(speed: f32) -> f32 {
+    speed * BURST_TICKS
+}
+
+pub fn distance(from: (f32, f32), to: (f32, f32)) -> f32 {
+    ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt()
+}
+
+pub fn sweep(terrain: &Terrain, from: (f32, f32), to: (f32, f32)) -> (f32, f32) {
+    let x = sweep_axis(terrain, from, to.0 - from.0, true);
+    let y = sweep_axis(terrain, (x, from.1), to.1 - from.1, false);
+    (x, y)
+}
+
+fn sweep_axis(terrain: &Terrain, position: (f32, f32), delta: f32, horizontal: bool) -> f32 {
+    let mut value = if horizontal { position.0 } else { position.1 };
+    if delta == 0.0 {
+        return value;
+    }
+
+    let steps = (delta.abs() / SWEEP_STEP).ceil() as u32;
+    let increment = delta / steps as f32;
+    for _ in 0..steps {
+        let next = value + increment;
+        let (x, y) = if horizontal { (next, position.1) } else { (position.0, next) };
+        if x < 0.0 || y < 0.0 || !pathfinding::is_walkable(terrain, x.floor() as usize, y.floor() as usize) {
+            return if increment > 0.0 {
+                (next.floor() - EDGE_EPSILON).max(value)
+            } else {
+                value.floor().min(value)
+            };
+        }
+        value = next;
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::tests::{map, tile_types};
+
+    #[test]
+    fn speed_grows_with_agility_up_to_cap() {
+        assert_eq!(speed(0), BASE_SPEED);
+        assert!((speed(10) - 1.2).abs() < 1e-6);
+        assert_eq!(speed(1000), MAX_SPEED);
+        assert_eq!(max_budget(1.5), 3.0);
+    }
+
+    #[test]
+    fn open_ground_reaches_target() {
+        let (map, tiles) = (map(&["...", "..."]), tile_types());
+        assert_eq!(sweep(&Terrain::walker(&map, &tiles), (0.5, 0.5), (2.5, 1.5)), (2.5, 1.5));
+    }
+
+    #[test]
+    fn wall_stops_movement_at_its_edge() {
+        let (map, tiles) = (map(&["..#"]), tile_types());
+        let (x, y) = sweep(&Terrain::walker(&map, &tiles), (0.5, 0.5), (2.5, 0.5));
+        assert!(x < 2.0 && x > 1.9);
+        assert_eq!(y, 0.5);
+    }
+
+    #[test]
+    fn moving_left_stops_at_tile_boundary() {
+        let (map, tiles) = (map(&["X.."]), tile_types());
+        assert_eq!(sweep(&Terrain::walker(&map, &tiles), (2.5, 0.5), (0.5, 0.5)), (1.0, 0.5));
+    }
+
+    #[test]
+    fn map_edge_blocks_movement() {
+        let (map, tiles) = (map(&["..", ".."]), tile_types());
+        let walker = Terrain::walker(&map, &tiles);
+        assert_eq!(sweep(&walker, (0.5, 0.5), (-1.0, 0.5)), (0.0, 0.5));
+        let (x, _) = sweep(&walker, (0.5, 0.5), (3.0, 0.5));
+        assert!(x < 2.0);
+    }
+
+    #[test]
+    fn blocked_axis_still_slides_along_the_other() {
+        let (map, tiles) = (map(&[
+            ".#",
+            "..",
+        ]), tile_types());
+        let (x, y) = sweep(&Terrain::walker(&map, &tiles), (0.5, 0.5), (1.5, 1.5));
+        assert!(x < 1.0);
+        assert_eq!(y, 1.5);
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Serialize, Deserialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::Map;
+use crate::tiles::{self, TileType};
+
+pub const BASE_COST: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Movement {
+    pub location: String,
+    pub path: Vec<(usize, usize)>,
+    pub budget: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Terrain<'a> {
+    pub map: &'a Map,
+    pub tiles: &'a [TileType],
+    pub swimmer: bool,
+}
+
+impl<'a> Terrain<'a> {
+    pub fn walker(map: &'a Map, tiles: &'a [TileType]) -> Self {
+        Terrain { map, tiles, swimmer: false }
+    }
+
+    pub fn swimmer(map: &'a Map, tiles: &'a [TileType]) -> Self {
+        Terrain { map, tiles, swimmer: true }
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Option<&'a TileType> {
+        tiles::find(self.tiles, self.map.tile(x, y)?)
+    }
+}
+
+pub fn tile_of(x: f32, y: f32) -> (usize, usize) {
+    (x.max(0.0).floor() as usize, y.max(0.0).floor() as usize)
+}
+
+pub fn is_walkable(terrain: &Terrain, x: usize, y: usize) -> bool {
+    x < terrain.map.width as usize
+        && y < terrain.map.height as usize
+        && !terrain.map.blocked(x, y)
+        && terrain.tile(x, y).is_some_and(|t| t.passable(terrain.swimmer))
+}
+
+pub fn step_cost(terrain: &Terrain, x: usize, y: usize) -> u32 {
+    terrain.tile(x, y).map_or(BASE_COST, |t| t.movement_cost)
+}
+
+pub fn neighbors(terrain: &Terrain, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+    let mut result = Vec::with_capacity(4);
+    if x > 0 && is_walkable(terrain, x - 1, y) {
+        result.push((x - 1, y));
+    }
+    if is_walkable(terrain, x + 1, y) {
+        result.push((x + 1, y));
+    }
+    if y > 0 && is_walkable(terrain, x, y - 1) {
+        result.push((x, y - 1));
+    }
+    if is_walkable(terrain, x, y + 1) {
+        result.push((x, y + 1));
+    }
+    result
+}
+
+fn heuristic(a: (usize, usize), b: (usize, usize)) -> u32 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u32 * BASE_COST
+}
+
+pub fn find_path(terrain: &Terrain, from: (usize, usize), to: (usize, usize)) -> Option<Vec<(usize, usize)>> {
+    if !is_walkable(terrain, from.0, from.1) || !is_walkable(terrain, to.0, to.1) {
+        return None;
+    }
+    if from == to {
+        return Some(Vec::new());
+    }
+
+    let mut best: HashMap<(usize, usize), u32> = HashMap::new();
+    let mut came_from: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut open = BinaryHeap::new();
+    best.insert(from, 0);
+    open.push(Reverse((heuristic(from, to), 0u32, from)));
+
+    while let Some(Reverse((_, cost, current))) = open.pop() {
+        if current == to {
+            let mut path = vec![current];
+            let mut step = current;
+            while let Some(&previous) = came_from.get(&step).filter(|&&p| p != from) {
+                path.push(previous);
+                step = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if cost > best.get(&current).copied().unwrap_or(u32::MAX) {
+            continue;
+        }
+        for next in neighbors(terrain, current) {
+            let next_cost = cost + step_cost(terrain, next.0, next.1);
+            if next_cost < best.get(&next).copied().unwrap_or(u32::MAX) {
+                best.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((next_cost + heuristic(next, to), next_cost, next)));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn tile_types() -> Vec<TileType> {
+        serde_json::from_value(serde_json::json!([
+            { "id": "grass", "name": "Grass" },
+            { "id": "sand", "name": "Sand", "movement_cost": 50 },
+            { "id": "stone", "name": "Stone", "walkable": false },
+            { "id": "water", "name": "Water", "walkable": false, "swimmable": true, "movement_cost": 20 },
+        ])).unwrap()
+    }
+
+    pub fn map(rows: &[&str]) -> Map {
+        let tile = |c: char| match c {
+            ',' => "sand",
+            '#' => "stone",
+            '~' => "water",
+            _ => "grass",
+        };
+        let tiles: Vec<Vec<&str>> = rows.iter().map(|r| r.chars().map(tile).collect()).collect();
+        let collisions: Vec<Vec<bool>> = rows.iter().map(|r| r.chars().map(|c| c == 'X').collect()).collect();
+        serde_json::from_value(serde_json::json!({
+            "width": rows[0].len(),
+            "height": rows.len(),
+            "tiles": tiles,
+            "collisions": collisions,
+        })).unwrap()
+    }
+
+    #[test]
+    fn walkability_respects_tiles_and_collisions() {
+        let (map, tiles) = (map(&[".#~X"]), tile_types());
+        let walker = Terrain::walker(&map, &tiles);
+        assert!(is_walkable(&walker, 0, 0));
+        assert!(!is_walkable(&walker, 1, 0));
+        assert!(!is_walkable(&walker, 2, 0));
+        assert!(!is_walkable(&walker, 3, 0));
+        assert!(!is_walkable(&walker, 4, 0));
+        assert!(is_walkable(&Terrain::swimmer(&map, &tiles), 2, 0));
+    }
+
+    #[test]
+    fn straight_path_excludes_start() {
+        let (map, tiles) = (map(&["...."]), tile_types());
+        let path = find_path(&Terrain::walker(&map, &tiles), (0, 0), (3, 0)).unwrap();
+        assert_eq!(path, vec![(1, 0), (2, 0), (3, 0)]);
+        assert_eq!(find_path(&Terrain::walker(&map, &tiles), (2, 0), (2, 0)), Some(Vec::new()));
+    }
+
+    #[test]
+    fn path_goes_around_walls() {
+        let (map, tiles) = (map(&[
+            ".#.",
+            ".#.",
+            "...",
+        ]), tile_types());
+        let path = find_path(&Terrain::walker(&map, &tiles), (0, 0), (2, 0)).unwrap();
+        assert_eq!(path.len(), 6);
+        assert!(path.iter().all(|&(x, y)| (x, y) != (1, 0) && (x, y) != (1, 1)));
+    }
+
+    #[test]
+    fn path_prefers_cheaper_tiles() {
+        let (map, tiles) = (map(&[
+            ".,.",
+            "...",
+        ]), tile_types());
+        let path = find_path(&Terrain::walker(&map, &tiles), (0, 0), (2, 0)).unwrap();
+        assert!(!path.contains(&(1, 0)));
+    }
+
+    #[test]
+    fn unreachable_or_blocked_targets_have_no_path() {
+        let (map, tiles) = (map(&[".#."]), tile_types());
+        let walker = Terrain::walker(&map, &tiles);
+        assert_eq!(find_path(&walker, (0, 0), (2, 0)), None);
+        assert_eq!(find_path(&walker, (0, 0), (1, 0)), None);
+        assert!(find_path(&Terrain::swimmer(&map, &tiles), (0, 0), (2, 0)).is_none());
+    }
+
+    #[test]
+    fn water_is_crossable_for_swimmers() {
+        let (map, tiles) = (map(&[".~."]), tile_types());
+        assert_eq!(find_path(&Terrain::walker(&map, &tiles), (0, 0), (2, 0)), None);
+        assert_eq!(find_path(&Terrain::swimmer(&map, &tiles), (0, 0), (2, 0)), Some(vec![(1, 0), (2, 0)]));
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Serialize, Deserialize};
+
+use crate::Map;
+use crate::pathfinding::{self, Terrain};
+use crate::validation::MapProblem;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Portal {
+    pub x: u32,
+    pub y: u32,
+    pub destination: String,
+    pub arrival_x: f32,
+    pub arrival_y: f32,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Portal {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.destination)
+    }
+}
+
+pub fn portal_at(map: &Map, x: f32, y: f32) -> Option<&Portal> {
+    let (tile_x, tile_y) = pathfinding::tile_of(x, y);
+    map.portals.iter().find(|p| p.x as usize == tile_x && p.y as usize == tile_y)
+}
+
+pub fn check_arrival(portal: &Portal, destination: &Terrain) -> Result<(), MapProblem> {
+    let (x, y) = pathfinding::tile_of(portal.arrival_x, portal.arrival_y);
+    if !destination.map.is_loaded(x, y) {
+        return Ok(());
+    }
+    if portal.arrival_x < 0.0 || portal.arrival_y < 0.0 || !pathfinding::is_walkable(destination, x, y) {
+        return Err(MapProblem::ArrivalBlocked { destination: portal.destination.clone(), x: portal.arrival_x, y: portal.arrival_y });
+    }
+    if portal_at(destination.map, portal.arrival_x, portal.arrival_y).is_some() {
+        return Err(MapProblem::ArrivalOnPortal { destination: portal.destination.clone(), x: portal.arrival_x, y: portal.arrival_y });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::tests::{map, tile_types};
+
+    fn portal(x: u32, y: u32, arrival: (f32, f32)) -> Portal {
+        Portal { x, y, destination: "Town".to_string(), arrival_x: arrival.0, arrival_y: arrival.1, name: None }
+    }
+
+    #[test]
+    fn label_prefers_name() {
+        assert_eq!(portal(0, 0, (0.0, 0.0)).label(), "Town");
+        assert_eq!(Portal { name: Some("East Gate".to_string()), ..portal(0, 0, (0.0, 0.0)) }.label(), "East Gate");
+    }
+
+    #[test]
+    fn portal_is_found_anywhere_on_its_tile() {
+        let mut map = map(&["...", "..."]);
+        map.portals.push(portal(2, 1, (0.5, 0.5)));
+        assert!(portal_at(&map, 2.0, 1.0).is_some());
+        assert!(portal_at(&map, 2.9, 1.9).is_some());
+        assert!(portal_at(&map, 1.9, 1.5).is_none());
+    }
+
+    #[test]
+    fn walkable_arrival_is_accepted() {
+        let (map, tiles) = (map(&["..."]), tile_types());
+        assert!(check_arrival(&portal(0, 0, (1.5, 0.5)), &Terrain::swimmer(&map, &tiles)).is_ok());
+    }
+
+    #[test]
+    fn blocked_or_outside_arrival_is_rejected() {
+        let (map, tiles) = (map(&[".#X"]), tile_types());
+        let terrain = Terrain::swimmer(&map, &tiles);
+        for arrival in [(1.5, 0.5), (2.5, 0.5), (-0.5, 0.5), (5.5, 0.5)] {
+            assert!(matches!(check_arrival(&portal(0, 0, arrival), &terrain), Err(MapProblem::ArrivalBlocked { .. })));
+        }
+    }
+
+    #[test]
+    fn arrival_on_a_portal_is_rejected() {
+        let (mut map, tiles) = (map(&["..."]), tile_types());
+        map.portals.push(portal(1, 0, (0.5, 0.5)));
+        let result = check_arrival(&portal(0, 0, (1.5, 0.5)), &Terrain::swimmer(&map, &tiles));
+        assert!(matches!(result, Err(MapProblem::ArrivalOnPortal { .. })));
+    }
+
+    #[test]
+    fn arrival_in_unloaded_chunk_is_deferred() {
+        let tiles = tile_types();
+        let map: Map = serde_json::from_value(serde_json::json!({ "width": 4, "height": 4, "chunk_size": 2 })).unwrap();
+        assert!(check_arrival(&portal(0, 0, (3.5, 3.5)), &Terrain::swimmer(&map, &tiles)).is_ok());
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Character;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExperienceCurve {
+    pub base: u64,
+    pub exponent: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Progression {
+    #[serde(default = "default_level_cap")]
+    pub level_cap: u32,
+    #[serde(default)]
+    pub curve: ExperienceCurve,
+    #[serde(default = "default_skill_points")]
+    pub skill_points_per_level: u32,
+    #[serde(default)]
+    pub race_growth: HashMap<String, HashMap<String, u32>>,
+    #[serde(default)]
+    pub profession_growth: HashMap<String, HashMap<String, u32>>,
+    #[serde(default = "default_skill_cap")]
+    pub default_skill_cap: u32,
+    #[serde(default)]
+    pub skill_caps: HashMap<String, u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct LevelUp {
+    pub experience_gained: u64,
+    pub levels_gained: u32,
+    pub new_level: u32,
+    pub skill_points: u32,
+    pub stat_gains: BTreeMap<String, u32>,
+}
+
+fn default_level_cap() -> u32 {
+    50
+}
+
+fn default_skill_points() -> u32 {
+    1
+}
+
+fn default_skill_cap() -> u32 {
+    100
+}
+
+impl Default for ExperienceCurve {
+    fn default() -> Self {
+        ExperienceCurve { base: 100, exponent: 1.0 }
+    }
+}
+
+impl Default for Progression {
+    fn default() -> Self {
+        Progression {
+            level_cap: default_level_cap(),
+            curve: ExperienceCurve::default(),
+            skill_points_per_level: default_skill_points(),
+            race_growth: HashMap::new(),
+            profession_growth: HashMap::new(),
+            default_skill_cap: default_skill_cap(),
+            skill_caps: HashMap::new(),
+        }
+    }
+}
+
+impl Progression {
+    pub fn experience_for_level(&self, level: u32) -> u64 {
+        (self.curve.base as f64 * (level as f64).powf(self.curve.exponent)).round() as u64
+    }
+
+    pub fn skill_cap(&self, skill: &str) -> u32 {
+        *self.skill_caps.get(skill).unwrap_or(&self.default_skill_cap)
+    }
+
+    pub fn growth_for(&self, character: &Character) -> BTreeMap<String, u32> {
+        let mut growth = BTreeMap::new();
+        for table in [self.race_growth.get(character.race.name()), self.profession_growth.get(character.profession.name())].into_iter().flatten() {
+            for (stat, value) in table {
+                *growth.entry(stat.clone()).or_insert(0) += value;
+            }
+        }
+        growth
+    }
+}
+
+pub fn grant_experience(character: &mut Character, amount: u64, progression: &Progression) -> LevelUp {
+    let mut result = LevelUp { new_level: character.level, ..LevelUp::default() };
+    if character.level >= progression.level_cap {
+        return result;
+    }
+
+    character.experience += amount;
+    result.experience_gained = amount;
+    let growth = progression.growth_for(character);
+
+    while character.level < progression.level_cap && character.experience >= progression.experience_for_level(character.level) {
+        character.level += 1;
+        character.skill_points += progression.skill_points_per_level;
+        result.levels_gained += 1;
+        result.skill_points += progression.skill_points_per_level;
+        for (stat, value) in &growth {
+            if let Some(base) = character.base_stat_mut(stat) {
+                *base += value;
+                *result.stat_gains.entry(stat.clone()).or_insert(0) += value;
+            }
+        }
+    }
+
+    if character.level >= progression.level_cap {
+        character.experience = character.experience.min(progression.experience_for_level(progression.level_cap - 1));
+    }
+    result.new_level = character.level;
+    result
+}
+
+pub fn describe_level_up(level_up: &LevelUp) -> Option<String> {
+    if level_up.levels_gained == 0 {
+        return None;
+    }
+    let gains: Vec<String> = level_up.stat_gains.iter().map(|(stat, value)| format!("+{} {}", value, stat)).collect();
+    Some(format!("Reached level {}! +{} skill points{}{}.", level_up.new_level, level_up.skill_points, if gains.is_empty() { "" } else { ", " }, gains.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn character() -> Character {
+        let mut game = Game::new();
+        game.create_character("Hero".to_string(), "Elf".to_string(), "Warrior".to_string(), "hero-wallet".to_string()).unwrap();
+        game.character.unwrap()
+    }
+
+    fn progression() -> Progression {
+        Progression {
+            level_cap: 5,
+            race_growth: HashMap::from([("Elf".to_string(), HashMap::from([("agility".to_string(), 2)]))]),
+            profession_growth: HashMap::from([("Warrior".to_string(), HashMap::from([("strength".to_string(), 1), ("agility".to_string(), 1)]))]),
+            ..Progression::default()
+        }
+    }
+
+    #[test]
+    fn curve_scales_with_level() {
+        let progression = Progression { curve: ExperienceCurve { base: 100, exponent: 2.0 }, ..Progression::default() };
+        assert_eq!(progression.experience_for_level(1), 100);
+        assert_eq!(progression.experience_for_level(3), 900);
+    }
+
+    #[test]
+    fn growth_combines_race_and_profession() {
+        let growth = progression().growth_for(&character());
+        assert_eq!(growth.get("agility"), Some(&3));
+        assert_eq!(growth.get("strength"), Some(&1));
+    }
+
+    #[test]
+    fn small_gain_does_not_level() {
+        let mut character = character();
+        let level_up = grant_experience(&mut character, 50, &progression());
+        assert_eq!((level_up.levels_gained, character.level, character.experience), (0, 1, 50));
+        assert_eq!(describe_level_up(&level_up), None);
+    }
+
+    #[test]
+    fn large_gain_levels_several_times() {
+        let mut character = character();
+        let (strength, skill_points) = (character.strength, character.skill_points);
+        let level_up = grant_experience(&mut character, 300, &progression());
+        assert_eq!((level_up.levels_gained, level_up.new_level), (3, 4));
+        assert_eq!(character.strength, strength + 3);
+        assert_eq!(character.skill_points, skill_points + 3);
+        assert_eq!(level_up.stat_gains.get("agility"), Some(&9));
+    }
+
+    #[test]
+    fn level_cap_stops_progress() {
+        let mut character = character();
+        grant_experience(&mut character, 10_000, &progression());
+        assert_eq!(character.level, 5);
+        assert_eq!(character.experience, progression().experience_for_level(4));
+        let level_up = grant_experience(&mut character, 100, &progression());
+        assert_eq!((level_up.experience_gained, character.experience), (0, 400));
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Serialize, Deserialize};
+use rand::prelude::*;
+
+use crate::combat::{self, Combatant};
+use crate::stats::StatSheet;
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct CombatProfile {
+    pub health: u32,
+    pub max_health: u32,
+    pub combatant: Combatant,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PvpRules {
+    #[serde(default = "default_max_level_difference")]
+    pub max_level_difference: u32,
+    #[serde(default = "default_cooldown_ticks")]
+    pub cooldown_ticks: u64,
+    #[serde(default = "default_max_rounds")]
+    pub max_rounds: u32,
+    #[serde(default = "default_gold_loss_percent")]
+    pub gold_loss_percent: u32,
+    #[serde(default = "default_item_drop_chance")]
+    pub item_drop_chance: f64,
+    #[serde(default = "default_defeat_ticks")]
+    pub defeat_ticks: u64,
+    #[serde(default = "default_range")]
+    pub range: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PvpResult {
+    pub attacker_wallet: String,
+    pub attacker_name: String,
+    pub target_wallet: String,
+    pub target_name: String,
+    pub winner: Option<String>,
+    pub rounds: u32,
+    pub attacker_health: u32,
+    pub target_health: u32,
+    #[serde(default)]
+    pub unprovoked: bool,
+    pub log: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PvpLoss {
+    pub gold: u64,
+    pub item_id: Option<u32>,
+}
+
+fn default_max_level_difference() -> u32 {
+    10
+}
+
+fn default_cooldown_ticks() -> u64 {
+    300
+}
+
+fn default_max_rounds() -> u32 {
+    20
+}
+
+fn default_gold_loss_percent() -> u32 {
+    10
+}
+
+fn default_item_drop_chance() -> f64 {
+    0.25
+}
+
+fn default_defeat_ticks() -> u64 {
+    30
+}
+
+fn default_range() -> f32 {
+    2.0
+}
+
+impl Default for PvpRules {
+    fn default() -> Self {
+        PvpRules {
+            max_level_difference: default_max_level_difference(),
+            cooldown_ticks: default_cooldown_ticks(),
+            max_rounds: default_max_rounds(),
+            gold_loss_percent: default_gold_loss_percent(),
+            item_drop_chance: default_item_drop_chance(),
+            defeat_ticks: default_defeat_ticks(),
+            range: default_range(),
+        }
+    }
+}
+
+impl CombatProfile {
+    pub fn new(sheet: &StatSheet, health: u32) -> CombatProfile {
+        CombatProfile {
+            health,
+            max_health: sheet.max_health,
+            combatant: Combatant::from_sheet(sheet),
+        }
+    }
+}
+
+pub fn simulate(rng: &mut impl Rng, attacker: (&str, CombatProfile), target: (&str, CombatProfile), max_rounds: u32) -> (u32, u32, u32, Vec<String>) {
+    let (attacker_name, attacker) = attacker;
+    let (target_name, target) = target;
+    let (mut attacker_health, mut target_health) = (attacker.health, target.health);
+    let mut log = Vec::new();
+    let mut rounds = 0;
+
+    while rounds < max_rounds && attacker_health > 0 && target_health > 0 {
+        rounds += 1;
+        let roll = combat::roll_attack(rng, &attacker.combatant, &target.combatant);
+        log.push(combat::describe_attack(attacker_name, target_name, &roll));
+        target_health = target_health.saturating_sub(roll.damage);
+        if target_health == 0 {
+            break;
+        }
+
+        let counter = combat::roll_attack(rng, &target.combatant, &attacker.combatant);
+        log.push(combat::describe_attack(target_name, attacker_name, &counter));
+        attacker_health = attacker_health.saturating_sub(counter.damage);
+    }
+
+    (attacker_health, target_health, rounds, log)
+}
@@ -0,0 +1,20 @@
+use rand::prelude::*;
+
+use crate::Character;
+use crate::progression::Progression;
+
+pub fn gain_chance(skill: u32, difficulty: u32) -> f64 {
+    let target = (difficulty * 10 + 20) as f64;
+    ((target - skill as f64) / 40.0).clamp(0.0, 0.9)
+}
+
+pub fn try_improve(rng: &mut impl Rng, character: &mut Character, skill: &str, difficulty: u32, progression: &Progression) -> Option<String> {
+    let cap = progression.skill_cap(skill);
+    let value = character.base_stat_mut(skill)?;
+    if *value >= cap || !rng.gen_bool(gain_chance(*value, difficulty)) {
+        return None;
+    }
+
+    *value += 1;
+    Some(format!("Your {} skill increased to {}.", skill.replace('_', " "), value))
+}
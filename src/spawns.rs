@@ -0,0 +1,100 @@
+use serde::{Serialize, Deserialize};
+use rand::prelude::*;
+
+use crate::Monster;
+use crate::ai::AiState;
+use crate::combat;
+use crate::pathfinding::{self, Terrain};
+
+pub const ENGAGE_RANGE: f32 = 1.5;
+const PLACEMENT_ATTEMPTS: u32 = 20;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SpawnArea {
+    pub id: u32,
+    pub location: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub monsters: Vec<u32>,
+    #[serde(default = "default_max_population")]
+    pub max_population: u32,
+    #[serde(default = "default_respawn_ticks")]
+    pub respawn_ticks: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MonsterInstance {
+    pub instance_id: u32,
+    pub monster_id: u32,
+    pub spawn_id: u32,
+    pub name: String,
+    pub level: u32,
+    pub location: String,
+    pub x: f32,
+    pub y: f32,
+    pub spawn_x: f32,
+    pub spawn_y: f32,
+    pub health: u32,
+    pub max_health: u32,
+    pub state: AiState,
+    pub last_action_tick: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct PendingRespawn {
+    pub spawn_id: u32,
+    pub due_tick: u64,
+}
+
+fn default_max_population() -> u32 {
+    3
+}
+
+fn default_respawn_ticks() -> u64 {
+    60
+}
+
+impl MonsterInstance {
+    pub fn distance_to(&self, x: f32, y: f32) -> f32 {
+        ((self.x - x).powi(2) + (self.y - y).powi(2)).sqrt()
+    }
+}
+
+pub fn find_spawn_position(rng: &mut impl Rng, area: &SpawnArea, terrain: &Terrain) -> Option<(f32, f32)> {
+    let max_x = (area.x + area.width).min(terrain.map.width);
+    let max_y = (area.y + area.height).min(terrain.map.height);
+    if area.x >= max_x || area.y >= max_y {
+        return None;
+    }
+
+    (0..PLACEMENT_ATTEMPTS).find_map(|_| {
+        let x = rng.gen_range(area.x..max_x);
+        let y = rng.gen_range(area.y..max_y);
+        pathfinding::is_walkable(terrain, x as usize, y as usize).then_some((x as f32, y as f32))
+    })
+}
+
+pub fn spawn_instance(rng: &mut impl Rng, instance_id: u32, area: &SpawnArea, monsters: &[Monster], terrain: &Terrain) -> Option<MonsterInstance> {
+    let monster_id = *area.monsters.choose(rng)?;
+    let monster = monsters.iter().find(|m| m.id == monster_id)?;
+    let (x, y) = find_spawn_position(rng, area, terrain)?;
+    let health = combat::monster_stat(monster, "health").max(1);
+    Some(MonsterInstance {
+        instance_id,
+        monster_id,
+        spawn_id: area.id,
+        name: monster.name.clone(),
+        level: monster.level,
+        location: area.location.clone(),
+        x,
+        y,
+        spawn_x: x,
+        spawn_y: y,
+        health,
+        max_health: health,
+        state: AiState::Idle,
+        last_action_tick: 0,
+    })
+}
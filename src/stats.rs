@@ -0,0 +1,154 @@
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+use crate::{Character, Equipment, Item, Race};
+
+pub const STAT_NAMES: [&str; 21] = [
+    "strength", "endurance", "wisdom", "mystic", "agility", "accuracy", "intellect", "luck",
+    "sword", "spear", "axe", "dagger", "bow", "shield_skill", "magic", "rune_magic",
+    "magic_resistance", "healing", "mining", "fishing", "alchemy",
+];
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StatSheet {
+    pub base: BTreeMap<String, u32>,
+    pub race: BTreeMap<String, u32>,
+    pub equipment: BTreeMap<String, u32>,
+    pub skill_tree: BTreeMap<String, u32>,
+    pub buffs: BTreeMap<String, i32>,
+    pub total: BTreeMap<String, u32>,
+    pub max_health: u32,
+    pub max_mana: u32,
+    pub attack: u32,
+    pub defense: u32,
+    pub crit_chance: f64,
+    pub magic_resistance: u32,
+}
+
+impl StatSheet {
+    pub fn compute(character: &Character, items: &[Item]) -> StatSheet {
+        let base: BTreeMap<String, u32> = STAT_NAMES.iter().map(|&s| (s.to_string(), character.base_stat(s))).collect();
+        let race = race_bonuses(&character.race).iter().map(|&(s, v)| (s.to_string(), v)).collect();
+        let equipment = equipment_bonuses(&character.equipment, items);
+        let skill_tree = skill_tree_bonuses(character);
+
+        let mut buffs = BTreeMap::new();
+        for effect in &character.effects {
+            for (stat, value) in &effect.modifiers {
+                *buffs.entry(stat.clone()).or_insert(0) += value * effect.stacks as i32;
+            }
+        }
+
+        let mut sheet = StatSheet {
+            base,
+            race,
+            equipment,
+            skill_tree,
+            buffs,
+            total: BTreeMap::new(),
+            max_health: 0,
+            max_mana: 0,
+            attack: 0,
+            defense: 0,
+            crit_chance: 0.0,
+            magic_resistance: 0,
+        };
+
+        let mut keys: Vec<String> = STAT_NAMES.iter().map(|s| s.to_string()).collect();
+        keys.extend(sheet.equipment.keys().chain(sheet.skill_tree.keys()).chain(sheet.buffs.keys()).cloned());
+        keys.sort();
+        keys.dedup();
+        for key in keys {
+            let flat = sheet.base.get(&key).unwrap_or(&0) + sheet.race.get(&key).unwrap_or(&0) + sheet.equipment.get(&key).unwrap_or(&0) + sheet.skill_tree.get(&key).unwrap_or(&0);
+            let total = (flat as i64 + *sheet.buffs.get(&key).unwrap_or(&0) as i64).max(0) as u32;
+            sheet.total.insert(key, total);
+        }
+
+        sheet.max_health = 50 + sheet.stat("endurance") * 5 + character.level * 10 + sheet.stat("health");
+        sheet.max_mana = 20 + sheet.stat("wisdom") * 3 + sheet.stat("mystic") * 2 + character.level * 5 + sheet.stat("mana");
+        let weapon_skill = crate::weapon_skill(&character.equipment, items).map_or(0, |skill| sheet.stat(&skill));
+        sheet.attack = sheet.stat("strength") / 2 + sheet.stat("accuracy") / 4 + weapon_skill / 5 + sheet.stat("attack");
+        sheet.defense = sheet.stat("endurance") / 4 + sheet.stat("shield_skill") / 5 + sheet.stat("defense");
+        sheet.crit_chance = (0.05 + sheet.stat("luck") as f64 * 0.01).clamp(0.0, 0.5);
+        sheet.magic_resistance = sheet.stat("magic_resistance") + sheet.stat("mystic") / 2 + sheet.stat("wisdom") / 4;
+        sheet
+    }
+
+    pub fn stat(&self, name: &str) -> u32 {
+        *self.total.get(name).unwrap_or(&0)
+    }
+}
+
+pub fn race_bonuses(race: &Race) -> &'static [(&'static str, u32)] {
+    match race {
+        Race::Human => &[("strength", 1), ("endurance", 1), ("wisdom", 1), ("intellect", 1), ("luck", 2)],
+        Race::Elf => &[("agility", 3), ("intellect", 2), ("mystic", 1), ("magic_resistance", 5)],
+        Race::Dwarf => &[("endurance", 3), ("strength", 2), ("magic_resistance", 3)],
+        Race::Orc => &[("strength", 4), ("endurance", 2)],
+    }
+}
+
+pub fn equipment_bonuses(equipment: &Equipment, items: &[Item]) -> BTreeMap<String, u32> {
+    let mut bonuses = BTreeMap::new();
+    for instance in equipment.slots().into_iter().flatten().filter(|i| !i.is_broken()) {
+        if let Some(item) = items.iter().find(|i| i.id == instance.item_id) {
+            for (stat, value) in &item.stats {
+                *bonuses.entry(stat.clone()).or_insert(0) += value;
+            }
+        }
+        for affix in &instance.affixes {
+            *bonuses.entry(affix.stat.clone()).or_insert(0) += affix.value;
+        }
+    }
+    bonuses
+}
+
+pub fn skill_tree_bonuses(character: &Character) -> BTreeMap<String, u32> {
+    let mut bonuses = BTreeMap::new();
+    for node in character.skill_tree.iter().filter(|n| n.unlocked) {
+        if let Some((stat, value)) = parse_skill_bonus(&node.skill) {
+            *bonuses.entry(stat).or_insert(0) += value;
+        }
+    }
+    bonuses
+}
+
+pub fn parse_skill_bonus(skill: &str) -> Option<(String, u32)> {
+    let (amount, stat) = skill.strip_prefix('+')?.split_once(' ')?;
+    let stat = match stat.to_lowercase().replace(' ', "_").as_str() {
+        "shield" => "shield_skill".to_string(),
+        other => other.to_string(),
+    };
+    Some((stat, amount.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    fn character() -> Character {
+        let mut game = Game::new();
+        game.create_character("Hero".to_string(), "Elf".to_string(), "Warrior".to_string(), "hero-wallet".to_string()).unwrap();
+        game.character.unwrap()
+    }
+
+    #[test]
+    fn skill_labels_parse_into_bonuses() {
+        assert_eq!(parse_skill_bonus("+5 Strength"), Some(("strength".to_string(), 5)));
+        assert_eq!(parse_skill_bonus("+3 Shield"), Some(("shield_skill".to_string(), 3)));
+        assert_eq!(parse_skill_bonus("Origin"), None);
+    }
+
+    #[test]
+    fn new_character_has_no_skill_tree_bonus() {
+        assert!(skill_tree_bonuses(&character()).is_empty());
+    }
+
+    #[test]
+    fn unlocked_nodes_grant_their_label() {
+        let mut character = character();
+        character.skill_tree[2].unlocked = true;
+        assert_eq!(skill_tree_bonuses(&character).get("endurance"), Some(&5));
+    }
+}
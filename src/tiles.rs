@@ -0,0 +1,69 @@
+use serde::{Serialize, Deserialize};
+
+use crate::pathfinding::BASE_COST;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TileType {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_walkable")]
+    pub walkable: bool,
+    #[serde(default = "default_movement_cost")]
+    pub movement_cost: u32,
+    #[serde(default)]
+    pub atlas_index: Option<u32>,
+    #[serde(default = "default_color")]
+    pub color: [f32; 4],
+    #[serde(default)]
+    pub resource: Option<u32>,
+    #[serde(default)]
+    pub swimmable: bool,
+    #[serde(default)]
+    pub damage_per_tick: u32,
+    #[serde(default = "default_footprint")]
+    pub footprint: [u32; 2],
+}
+
+fn default_walkable() -> bool {
+    true
+}
+
+fn default_movement_cost() -> u32 {
+    BASE_COST
+}
+
+fn default_color() -> [f32; 4] {
+    [1.0, 1.0, 1.0, 1.0]
+}
+
+fn default_footprint() -> [u32; 2] {
+    [1, 1]
+}
+
+impl TileType {
+    pub fn passable(&self, swimmer: bool) -> bool {
+        self.walkable || (swimmer && self.swimmable)
+    }
+}
+
+pub fn find<'a>(tiles: &'a [TileType], id: &str) -> Option<&'a TileType> {
+    tiles.iter().find(|t| t.id == id)
+}
+
+pub fn validate(tiles: &[TileType]) -> Result<(), String> {
+    if tiles.is_empty() {
+        return Err("No tile types defined".to_string());
+    }
+    for (index, tile) in tiles.iter().enumerate() {
+        if tiles[..index].iter().any(|t| t.id == tile.id) {
+            return Err(format!("Duplicate tile type {}", tile.id));
+        }
+        if tile.movement_cost == 0 && (tile.walkable || tile.swimmable) {
+            return Err(format!("Tile type {} must have a movement cost", tile.id));
+        }
+        if tile.footprint.contains(&0) {
+            return Err(format!("Tile type {} must cover at least one cell", tile.id));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+use serde::Serialize;
+
+use crate::Map;
+use crate::chunks::{self, Chunk};
+use crate::pathfinding::{self, Terrain};
+use crate::tiles::{self, TileType};
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MapProblem {
+    EmptyMap,
+    RowCount { grid: &'static str, expected: u32, found: usize },
+    RowLength { grid: &'static str, row: usize, expected: u32, found: usize },
+    UnknownTile { x: usize, y: usize, tile: String },
+    InvalidChunkSize,
+    ChunkOutOfBounds { x: u32, y: u32 },
+    ConflictingZone,
+    EmptyLevelRange { min_level: u32, max_level: u32 },
+    SpawnPointBlocked { x: f32, y: f32 },
+    PortalOutOfBounds { x: u32, y: u32, destination: String },
+    PortalBlocked { x: u32, y: u32, destination: String },
+    PortalUnreachable { x: u32, y: u32, destination: String },
+    ArrivalBlocked { destination: String, x: f32, y: f32 },
+    ArrivalOnPortal { destination: String, x: f32, y: f32 },
+    UnsafeRespawn { location: String },
+    SafeOutlawRespawn,
+    SpawnInSafeZone { spawn_id: u32 },
+    SpawnAreaBlocked { spawn_id: u32 },
+    SpawnLevelOutOfRange { spawn_id: u32, monster: String, level: u32 },
+    MisorderedLayer { layer: String },
+    UnknownLayerTile { layer: String, x: u32, y: u32, tile: String },
+    PlacementOutOfBounds { layer: String, x: u32, y: u32 },
+}
+
+#[derive(Serialize)]
+pub struct ProblemReport {
+    #[serde(flatten)]
+    pub problem: MapProblem,
+    pub message: String,
+}
+
+impl MapProblem {
+    pub fn describe(&self) -> String {
+        match self {
+            MapProblem::EmptyMap => "Map has no tiles".to_string(),
+            MapProblem::RowCount { grid, expected, found } => format!("{} has {} rows but the map height is {}", grid, found, expected),
+            MapProblem::RowLength { grid, row, expected, found } => format!("{} row {} has {} columns but the map width is {}", grid, row, found, expected),
+            MapProblem::UnknownTile { x, y, tile } => format!("Unknown tile type {} at {},{}", tile, x, y),
+            MapProblem::InvalidChunkSize => "Chunk size must be at least 1".to_string(),
+            MapProblem::ChunkOutOfBounds { x, y } => format!("Chunk {},{} is outside the map", x, y),
+            MapProblem::ConflictingZone => "Zone cannot be both safe and PvP".to_string(),
+            MapProblem::EmptyLevelRange { min_level, max_level } => format!("Zone has an empty level range {}-{}", min_level, max_level),
+            MapProblem::SpawnPointBlocked { x, y } => format!("Spawn point {},{} is not walkable", x, y),
+            MapProblem::PortalOutOfBounds { x, y, destination } => format!("Portal to {} at {},{} is outside the map", destination, x, y),
+            MapProblem::PortalBlocked { x, y, destination } => format!("Portal to {} at {},{} is on a blocked tile", destination, x, y),
+            MapProblem::PortalUnreachable { x, y, destination } => format!("Portal to {} at {},{} cannot be reached from the spawn point", destination, x, y),
+            MapProblem::ArrivalBlocked { destination, x, y } => format!("Portal arrival point {},{} in {} is not walkable", x, y, destination),
+            MapProblem::ArrivalOnPortal { destination, x, y } => format!("Portal arrival point {},{} in {} is itself a portal", x, y, destination),
+            MapProblem::UnsafeRespawn { location } => format!("Respawn point {} is not a safe zone", location),
+            MapProblem::SafeOutlawRespawn => "Outlaw respawn point must not be a safe zone".to_string(),
+            MapProblem::SpawnInSafeZone { spawn_id } => format!("Spawn area {} cannot be in a safe zone", spawn_id),
+            MapProblem::SpawnAreaBlocked { spawn_id } => format!("Spawn area {} has no walkable tile on the map", spawn_id),
+            MapProblem::SpawnLevelOutOfRange { spawn_id, monster, level } => format!("Spawn area {} places {} (Level {}) outside the zone level range", spawn_id, monster, level),
+            MapProblem::MisorderedLayer { layer } => format!("Layer {} is drawn below a layer of an earlier kind", layer),
+            MapProblem::UnknownLayerTile { layer, x, y, tile } => format!("Unknown tile type {} in layer {} at {},{}", tile, layer, x, y),
+            MapProblem::PlacementOutOfBounds { layer, x, y } => format!("Layer {} placement at {},{} extends outside the map", layer, x, y),
+        }
+    }
+
+    pub fn report(self) -> ProblemReport {
+        let message = self.describe();
+        ProblemReport { problem: self, message }
+    }
+}
+
+pub fn describe_problems(location: &str, problems: &[MapProblem]) -> String {
+    let details: Vec<String> = problems.iter().map(|p| p.describe()).collect();
+    format!("Map {} is invalid: {}", location, details.join("; "))
+}
+
+pub fn validate(location: &str, map: &Map, tile_types: &[TileType]) -> Vec<MapProblem> {
+    let mut problems = Vec::new();
+    if map.zone.pvp && map.zone.safe {
+        problems.push(MapProblem::ConflictingZone);
+    }
+    if let (Some(min_level), Some(max_level)) = (map.zone.min_level, map.zone.max_level) {
+        if min_level > max_level {
+            problems.push(MapProblem::EmptyLevelRange { min_level, max_level });
+        }
+    }
+    if map.width == 0 || map.height == 0 {
+        problems.push(MapProblem::EmptyMap);
+        return problems;
+    }
+    check_layers(map, tile_types, &mut problems);
+    if map.chunk_size.is_some() {
+        if map.chunk_size == Some(0) {
+            problems.push(MapProblem::InvalidChunkSize);
+        }
+        problems.extend(map.portals.iter().filter(|p| p.x >= map.width || p.y >= map.height).map(|p| MapProblem::PortalOutOfBounds { x: p.x, y: p.y, destination: p.destination.clone() }));
+        return problems;
+    }
+    let tiles_ok = check_grid("tiles", &map.tiles, (map.width, map.height), &mut problems);
+    let collisions_ok = check_grid("collisions", &map.collisions, (map.width, map.height), &mut problems);
+    if !tiles_ok || !collisions_ok {
+        return problems;
+    }
+    check_tiles(&map.tiles, (0, 0), tile_types, &mut problems);
+
+    let terrain = Terrain::swimmer(map, tile_types);
+    let mut spawn = None;
+    if let Some(point) = map.zone.respawn.as_ref().filter(|p| p.location == location) {
+        let (x, y) = pathfinding::tile_of(point.x, point.y);
+        if point.x < 0.0 || point.y < 0.0 || !pathfinding::is_walkable(&terrain, x, y) {
+            problems.push(MapProblem::SpawnPointBlocked { x: point.x, y: point.y });
+        } else {
+            spawn = Some((x, y));
+        }
+    }
+
+    for portal in &map.portals {
+        let (x, y, destination) = (portal.x, portal.y, portal.destination.clone());
+        if x >= map.width || y >= map.height {
+            problems.push(MapProblem::PortalOutOfBounds { x, y, destination });
+        } else if !pathfinding::is_walkable(&terrain, x as usize, y as usize) {
+            problems.push(MapProblem::PortalBlocked { x, y, destination });
+        } else if spawn.is_some_and(|start| pathfinding::find_path(&terrain, start, (x as usize, y as usize)).is_none()) {
+            problems.push(MapProblem::PortalUnreachable { x, y, destination });
+        }
+    }
+    problems
+}
+
+pub fn validate_chunk(map: &Map, (x, y): (u32, u32), chunk: &Chunk, tile_types: &[TileType]) -> Vec<MapProblem> {
+    let mut problems = Vec::new();
+    let Some(size) = map.chunk_size.filter(|&s| s > 0) else {
+        problems.push(MapProblem::InvalidChunkSize);
+        return problems;
+    };
+    let (columns, rows) = chunks::chunk_count(map, size);
+    if x >= columns || y >= rows {
+        problems.push(MapProblem::ChunkOutOfBounds { x, y });
+        return problems;
+    }
+    let dimensions = chunks::chunk_dimensions(map, size, (x, y));
+    let tiles_ok = check_grid("tiles", &chunk.tiles, dimensions, &mut problems);
+    let collisions_ok = check_grid("collisions", &chunk.collisions, dimensions, &mut problems);
+    if tiles_ok && collisions_ok {
+        check_tiles(&chunk.tiles, ((x * size) as usize, (y * size) as usize), tile_types, &mut problems);
+    }
+    problems
+}
+
+fn check_layers(map: &Map, tile_types: &[TileType], problems: &mut Vec<MapProblem>) {
+    for pair in map.layers.windows(2) {
+        if pair[1].kind < pair[0].kind {
+            problems.push(MapProblem::MisorderedLayer { layer: pair[1].name.clone() });
+        }
+    }
+    for layer in &map.layers {
+        for placement in &layer.placements {
+            let (x, y) = (placement.x, placement.y);
+            let Some(tile) = tiles::find(tile_types, &placement.tile) else {
+                problems.push(MapProblem::UnknownLayerTile { layer: layer.name.clone(), x, y, tile: placement.tile.clone() });
+                continue;
+            };
+            let [width, height] = tile.footprint;
+            if x.saturating_add(width) > map.width || y.saturating_add(height) > map.height {
+                problems.push(MapProblem::PlacementOutOfBounds { layer: layer.name.clone(), x, y });
+            }
+        }
+    }
+}
+
+fn check_tiles(rows: &[Vec<String>], origin: (usize, usize), tile_types: &[TileType], problems: &mut Vec<MapProblem>) {
+    for (y, row) in rows.iter().enumerate() {
+        for (x, tile) in row.iter().enumerate() {
+            if tiles::find(tile_types, tile).is_none() {
+                problems.push(MapProblem::UnknownTile { x: origin.0 + x, y: origin.1 + y, tile: tile.clone() });
+            }
+        }
+    }
+}
+
+fn check_grid<T>(grid: &'static str, rows: &[Vec<T>], (width, height): (u32, u32), problems: &mut Vec<MapProblem>) -> bool {
+    let mut ok = true;
+    if rows.len() != height as usize {
+        problems.push(MapProblem::RowCount { grid, expected: height, found: rows.len() });
+        ok = false;
+    }
+    for (row, cells) in rows.iter().enumerate() {
+        if cells.len() != width as usize {
+            problems.push(MapProblem::RowLength { grid, row, expected: width, found: cells.len() });
+            ok = false;
+        }
+    }
+    ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::death::RespawnPoint;
+    use crate::pathfinding::tests::{map, tile_types};
+    use crate::portals::Portal;
+
+    fn portal(x: u32, y: u32) -> Portal {
+        Portal { x, y, destination: "Town".to_string(), arrival_x: 0.5, arrival_y: 0.5, name: None }
+    }
+
+    fn chunked(width: u32, height: u32, size: u32) -> Map {
+        serde_json::from_value(serde_json::json!({ "width": width, "height": height, "chunk_size": size })).unwrap()
+    }
+
+    fn chunk(width: usize, height: usize) -> Chunk {
+        Chunk { tiles: vec![vec!["grass".to_string(); width]; height], collisions: vec![vec![false; width]; height] }
+    }
+
+    #[test]
+    fn valid_map_has_no_problems() {
+        let mut map = map(&["...", "..."]);
+        map.portals.push(portal(2, 1));
+        assert!(validate("Field", &map, &tile_types()).is_empty());
+    }
+
+    #[test]
+    fn zone_rules_are_checked() {
+        let mut map = map(&["."]);
+        map.zone.pvp = true;
+        map.zone.safe = true;
+        map.zone.min_level = Some(5);
+        map.zone.max_level = Some(3);
+        let problems = validate("Field", &map, &tile_types());
+        assert!(matches!(problems[..], [MapProblem::ConflictingZone, MapProblem::EmptyLevelRange { min_level: 5, max_level: 3 }]));
+    }
+
+    #[test]
+    fn grid_shape_must_match_size() {
+        let mut map = map(&["..", ".."]);
+        map.height = 3;
+        map.collisions[1].pop();
+        let problems = validate("Field", &map, &tile_types());
+        assert!(matches!(problems[0], MapProblem::RowCount { grid: "tiles", expected: 3, found: 2 }));
+        assert!(matches!(problems[2], MapProblem::RowLength { grid: "collisions", row: 1, expected: 2, found: 1 }));
+        map.width = 0;
+        assert!(matches!(validate("Field", &map, &tile_types())[..], [MapProblem::EmptyMap]));
+    }
+
+    #[test]
+    fn unknown_tiles_are_reported_with_position() {
+        let mut map = map(&["..", ".."]);
+        map.tiles[1][0] = "mud".to_string();
+        assert!(matches!(&validate("Field", &map, &tile_types())[..], [MapProblem::UnknownTile { x: 0, y: 1, tile }] if tile == "mud"));
+    }
+
+    #[test]
+    fn portals_must_be_inside_walkable_and_reachable() {
+        let mut map = map(&[
+            ".#.",
+            "##.",
+        ]);
+        map.zone.respawn = Some(RespawnPoint { location: "Field".to_string(), x: 0.5, y: 0.5 });
+        map.portals = vec![portal(5, 0), portal(1, 0), portal(2, 1)];
+        let problems = validate("Field", &map, &tile_types());
+        assert!(matches!(problems[..], [
+            MapProblem::PortalOutOfBounds { x: 5, .. },
+            MapProblem::PortalBlocked { x: 1, .. },
+            MapProblem::PortalUnreachable { x: 2, y: 1, .. },
+        ]));
+    }
+
+    #[test]
+    fn respawn_point_must_be_walkable() {
+        let mut map = map(&[".#"]);
+        map.zone.respawn = Some(RespawnPoint { location: "Field".to_string(), x: 1.5, y: 0.5 });
+        assert!(matches!(validate("Field", &map, &tile_types())[..], [MapProblem::SpawnPointBlocked { .. }]));
+    }
+
+    #[test]
+    fn layers_are_checked() {
+        let mut tiles = tile_types();
+        tiles.push(serde_json::from_value(serde_json::json!({ "id": "table", "name": "Table", "walkable": false, "footprint": [2, 1] })).unwrap());
+        let mut map = map(&["...", "..."]);
+        map.layers = serde_json::from_value(serde_json::json!([
+            { "name": "roof", "kind": "roof" },
+            { "name": "furniture", "kind": "objects", "placements": [
+                { "x": 2, "y": 0, "tile": "table" },
+                { "x": 0, "y": 1, "tile": "throne" },
+            ] },
+        ])).unwrap();
+        let problems = validate("Field", &map, &tiles);
+        assert!(matches!(&problems[..], [
+            MapProblem::MisorderedLayer { layer },
+            MapProblem::PlacementOutOfBounds { x: 2, y: 0, .. },
+            MapProblem::UnknownLayerTile { tile, .. },
+        ] if layer == "furniture" && tile == "throne"));
+    }
+
+    #[test]
+    fn chunked_maps_skip_grid_checks() {
+        let mut map = chunked(4, 4, 2);
+        map.portals.push(portal(4, 0));
+        assert!(matches!(validate("World", &map, &tile_types())[..], [MapProblem::PortalOutOfBounds { .. }]));
+        map.chunk_size = Some(0);
+        assert!(matches!(validate("World", &map, &tile_types())[0], MapProblem::InvalidChunkSize));
+    }
+
+    #[test]
+    fn chunks_must_fit_their_slot() {
+        let map = chunked(5, 4, 3);
+        let tiles = tile_types();
+        assert!(validate_chunk(&map, (0, 0), &chunk(3, 3), &tiles).is_empty());
+        assert!(validate_chunk(&map, (1, 1), &chunk(2, 1), &tiles).is_empty());
+        assert!(matches!(validate_chunk(&map, (1, 0), &chunk(3, 3), &tiles)[..], [
+            MapProblem::RowLength { grid: "tiles", .. }, _, _,
+            MapProblem::RowLength { grid: "collisions", .. }, _, _,
+        ]));
+        assert!(matches!(validate_chunk(&map, (2, 0), &chunk(3, 3), &tiles)[..], [MapProblem::ChunkOutOfBounds { x: 2, y: 0 }]));
+        assert!(matches!(validate_chunk(&chunked(5, 4, 0), (0, 0), &chunk(3, 3), &tiles)[..], [MapProblem::InvalidChunkSize]));
+    }
+
+    #[test]
+    fn chunk_tiles_report_world_positions() {
+        let mut piece = chunk(2, 1);
+        piece.tiles[0][1] = "mud".to_string();
+        let problems = validate_chunk(&chunked(5, 4, 3), (1, 1), &piece, &tile_types());
+        assert!(matches!(problems[..], [MapProblem::UnknownTile { x: 4, y: 3, .. }]));
+    }
+}
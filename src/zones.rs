@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::Map;
+use crate::death::RespawnPoint;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Zone {
+    #[serde(default)]
+    pub pvp: bool,
+    #[serde(default)]
+    pub safe: bool,
+    #[serde(default)]
+    pub min_level: Option<u32>,
+    #[serde(default)]
+    pub max_level: Option<u32>,
+    #[serde(default)]
+    pub music: Option<String>,
+    #[serde(default)]
+    pub ambient: Option<String>,
+    #[serde(default)]
+    pub respawn: Option<RespawnPoint>,
+    #[serde(default)]
+    pub faction: Option<String>,
+    #[serde(default)]
+    pub indoors: bool,
+}
+
+static UNCHARTED: Zone = Zone {
+    pvp: false,
+    safe: false,
+    min_level: None,
+    max_level: None,
+    music: None,
+    ambient: None,
+    respawn: None,
+    faction: None,
+    indoors: false,
+};
+
+impl Zone {
+    pub fn allows_level(&self, level: u32) -> bool {
+        level >= self.min_level.unwrap_or(0) && level <= self.max_level.unwrap_or(u32::MAX)
+    }
+
+    pub fn describe(&self, location: &str) -> String {
+        let mut tags = vec![if self.safe { "Safe" } else if self.pvp { "PvP" } else { "Non-PvP" }.to_string()];
+        match (self.min_level, self.max_level) {
+            (Some(min), Some(max)) => tags.push(format!("Lv {}-{}", min, max)),
+            (Some(min), None) => tags.push(format!("Lv {}+", min)),
+            (None, Some(max)) => tags.push(format!("Lv 1-{}", max)),
+            (None, None) => {}
+        }
+        if let Some(faction) = &self.faction {
+            tags.push(faction.clone());
+        }
+        format!("{} ({})", location, tags.join(", "))
+    }
+}
+
+pub fn zone_of<'a>(maps: &'a HashMap<String, Map>, location: &str) -> &'a Zone {
+    maps.get(location).map_or(&UNCHARTED, |m| &m.zone)
+}
@@ -2,9 +2,11 @@ const WebSocket = require('ws');
 
 const wss = new WebSocket.Server({ port: 8080 });
 const players = new Map();
+const sockets = new Map();
 
 wss.on('connection', (ws, req) => {
     const wallet = req.url.slice(1);
+    sockets.set(wallet, ws);
     console.log(`Player connected: ${wallet}`);
 
     ws.on('message', (message) => {
@@ -18,6 +20,12 @@ wss.on('connection', (ws, req) => {
                 y: data.y,
                 location: data.location,
                 equipment: data.equipment,
+                combat: data.combat,
+                defeated: data.defeated,
+                karma: data.karma,
+                outlaw: data.outlaw,
+                hostile: data.hostile,
+                arena: data.arena,
             });
 
             const playerList = Array.from(players.values());
@@ -26,11 +34,36 @@ wss.on('connection', (ws, req) => {
                     client.send(JSON.stringify({ type: 'players', players: playerList }));
                 }
             });
+        } else if (data.type === 'pvp_result') {
+            const target = sockets.get(data.target);
+            if (target && target.readyState === WebSocket.OPEN && data.result && data.result.attacker_wallet === wallet) {
+                target.send(JSON.stringify({ type: 'pvp_result', result: data.result }));
+            }
+        } else if (data.type === 'boss_credit') {
+            if (data.kill && data.kill.killer_wallet === wallet) {
+                for (const participant of data.kill.participants) {
+                    const target = sockets.get(participant);
+                    if (target && target.readyState === WebSocket.OPEN) {
+                        target.send(JSON.stringify({ type: 'boss_credit', kill: data.kill }));
+                    }
+                }
+            }
+        } else if (data.type === 'duel_request') {
+            const target = sockets.get(data.target);
+            if (target && target.readyState === WebSocket.OPEN && data.request && data.request.challenger_wallet === wallet) {
+                target.send(JSON.stringify({ type: 'duel_request', request: data.request }));
+            }
+        } else if (data.type === 'duel_result') {
+            const target = sockets.get(data.target);
+            if (target && target.readyState === WebSocket.OPEN && data.result && data.result.target_wallet === wallet) {
+                target.send(JSON.stringify({ type: 'duel_result', result: data.result }));
+            }
         }
     });
 
     ws.on('close', () => {
         players.delete(wallet);
+        sockets.delete(wallet);
         const playerList = Array.from(players.values());
         wss.clients.forEach((client) => {
             if (client.readyState === WebSocket.OPEN) {